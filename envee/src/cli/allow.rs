@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::cli::Task;
+use crate::cli::hook::FILE;
+use crate::env::{self, Resolver};
+use crate::trust::Trust;
+
+#[derive(Debug, Parser)]
+/// let the shell hook load an env file, needed again whenever it or a file
+/// it extends changes
+pub struct Allow {
+    /// env file to allow, defaults to the envee.toml the hook would load
+    path: Option<PathBuf>,
+}
+
+impl Task for Allow {
+    fn run(&self) -> Result<ExitCode> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => env::discover(&std::env::current_dir()?, FILE)
+                .with_context(|| format!("no {FILE} in this directory or its parents"))?,
+        };
+        let path = fs::canonicalize(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let paths = Resolver::new(vec![path.clone()]).paths()?;
+        Trust::load()?.allow(&path, Trust::hash(&paths)?)?;
+        println!("allowed {}", path.display());
+        Ok(ExitCode::SUCCESS)
+    }
+}
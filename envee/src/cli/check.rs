@@ -0,0 +1,32 @@
+use std::process::ExitCode;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::cli::{self, Source, Task};
+use crate::env::Violations;
+
+#[derive(Debug, Parser)]
+/// check the env against its schema
+pub struct Check {
+    #[command(flatten)]
+    source: Source,
+}
+
+impl Task for Check {
+    fn run(&self) -> Result<ExitCode> {
+        match self.source.resolver()?.resolve() {
+            Ok(variables) => {
+                cli::warn(&variables);
+                Ok(ExitCode::SUCCESS)
+            }
+            Err(error) => match error.downcast::<Violations>() {
+                Ok(violations) => {
+                    eprintln!("{violations}");
+                    Ok(ExitCode::FAILURE)
+                }
+                Err(error) => Err(error),
+            },
+        }
+    }
+}
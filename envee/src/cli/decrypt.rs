@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::cli::document::Document;
+use crate::cli::{Secret, Task};
+
+#[derive(Debug, Parser)]
+/// decrypt every encrypted value in an env file back to plain text
+pub struct Decrypt {
+    /// toml file to edit
+    #[arg(short, long)]
+    file: PathBuf,
+
+    #[command(flatten)]
+    secret: Secret,
+}
+
+impl Task for Decrypt {
+    fn run(&self) -> Result<ExitCode> {
+        let key = self.secret.required()?;
+        let mut document = Document::open(&self.file)?;
+        for (path, encrypted) in document.encrypted() {
+            let plaintext = key
+                .decrypt(&path, &encrypted)
+                .with_context(|| format!("failed to decrypt {path}"))?;
+            document.decrypt(&path, &plaintext);
+        }
+        document.save()?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::Result;
+use clap::Parser;
+use serde_json::{Map, Value, json};
+
+use crate::cli::{self, Source, Task};
+use crate::env::Variable;
+use crate::redact::Redactor;
+
+#[derive(Debug, Parser)]
+/// compare two envs, exiting with 1 when they differ or 2 on errors
+pub struct Diff {
+    #[command(flatten)]
+    source: Source,
+
+    /// env file(s) of the first env, defaults to --files
+    #[arg(short = 'a', long)]
+    files_a: Vec<PathBuf>,
+
+    /// env file(s) of the second env, defaults to --files
+    #[arg(short = 'b', long)]
+    files_b: Vec<PathBuf>,
+
+    /// stage of the first env, defaults to --stage
+    #[arg(long)]
+    stage_a: Option<String>,
+
+    /// stage of the second env, defaults to --stage
+    #[arg(long)]
+    stage_b: Option<String>,
+
+    /// print the changes as a json object
+    #[arg(long)]
+    json: bool,
+
+    /// show the values of secrets instead of masking them
+    #[arg(long)]
+    reveal: bool,
+}
+
+/// variable that is only in one env or has different values
+#[derive(Debug)]
+enum Change {
+    Added(String, String),
+    Removed(String, String),
+    Changed(String, String, String),
+}
+
+impl Task for Diff {
+    fn run(&self) -> Result<ExitCode> {
+        let envs = self
+            .resolve(&self.files_a, self.stage_a.as_ref())
+            .and_then(|a| Ok((a, self.resolve(&self.files_b, self.stage_b.as_ref())?)));
+        // told apart from differences, as with diff(1)
+        let (a, b) = match envs {
+            Ok(envs) => envs,
+            Err(error) => {
+                eprintln!("Error: {error:?}");
+                return Ok(ExitCode::from(2));
+            }
+        };
+        cli::warn(a.iter().chain(&b));
+        let changes = self.changes(&a, &b);
+        match self.json {
+            true => println!("{}", serde_json::to_string_pretty(&Self::json(&changes))?),
+            false => print!("{}", Self::text(&changes)),
+        }
+        Ok(match changes.is_empty() {
+            true => ExitCode::SUCCESS,
+            false => ExitCode::FAILURE,
+        })
+    }
+}
+
+impl Diff {
+    fn resolve(&self, files: &[PathBuf], stage: Option<&String>) -> Result<Vec<Variable>> {
+        let files = match files.is_empty() {
+            true => &self.source.files,
+            false => files,
+        };
+        let stage = stage.or(self.source.stage.as_ref());
+        self.source.with(files, stage)?.resolve()
+    }
+
+    /// removed and changed variables in the order of the first env, then
+    /// the added ones in the order of the second
+    fn changes(&self, a: &[Variable], b: &[Variable]) -> Vec<Change> {
+        let mut result = Vec::default();
+        for old in a {
+            match b.iter().find(|new| new.key == old.key) {
+                None => result.push(Change::Removed(old.key.clone(), self.value(a, old, false))),
+                Some(new) if new.value != old.value => {
+                    let secret = old.secret || new.secret;
+                    result.push(Change::Changed(
+                        old.key.clone(),
+                        self.value(a, old, secret),
+                        self.value(b, new, secret),
+                    ));
+                }
+                Some(_) => (),
+            }
+        }
+        for new in b {
+            if !a.iter().any(|old| old.key == new.key) {
+                result.push(Change::Added(new.key.clone(), self.value(b, new, false)));
+            }
+        }
+        result
+    }
+
+    /// value as shown, `secret` masks it even if only the other env marks
+    /// the variable as secret, secrets it references in its env are masked
+    /// where the value includes them
+    fn value(&self, env: &[Variable], variable: &Variable, secret: bool) -> String {
+        match (variable.secret || secret) && !self.reveal {
+            true => Redactor::MASK.to_string(),
+            false => cli::redactor(env, variable, self.reveal).redact_str(&variable.value),
+        }
+    }
+
+    fn text(changes: &[Change]) -> String {
+        let mut result = String::default();
+        for change in changes {
+            let line = match change {
+                Change::Added(key, value) => format!("+ {key} = {value:?}"),
+                Change::Removed(key, value) => format!("- {key} = {value:?}"),
+                Change::Changed(key, old, new) => format!("~ {key} = {old:?} -> {new:?}"),
+            };
+            result.push_str(&line);
+            result.push('\n');
+        }
+        result
+    }
+
+    fn json(changes: &[Change]) -> Value {
+        let mut added = Map::default();
+        let mut removed = Map::default();
+        let mut changed = Map::default();
+        for change in changes {
+            match change {
+                Change::Added(key, value) => {
+                    added.insert(key.clone(), json!(value));
+                }
+                Change::Removed(key, value) => {
+                    removed.insert(key.clone(), json!(value));
+                }
+                Change::Changed(key, old, new) => {
+                    changed.insert(key.clone(), json!({ "a": old, "b": new }));
+                }
+            }
+        }
+        json!({ "added": added, "removed": removed, "changed": changed })
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use toml_edit::{DocumentMut, InlineTable, Item, Value};
+
+use crate::env::{self, Format};
+
+/// toml file edited in place, keeping its comments and formatting
+#[derive(Debug)]
+pub struct Document {
+    path: PathBuf,
+    document: DocumentMut,
+}
+
+impl Document {
+    pub fn open(path: &Path) -> Result<Self> {
+        if Format::detect(path) != Format::Toml {
+            bail!("only toml files can be edited: {}", path.display());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let document = text
+            .parse()
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            document,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, self.document.to_string())
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    /// item at a dotted path as found in the origin of a definition
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut Item> {
+        let mut item = self.document.as_item_mut();
+        for segment in path.split('.') {
+            item = match segment.parse::<usize>() {
+                Ok(i) if item.is_array() || item.is_array_of_tables() => item.get_mut(i)?,
+                _ => item.get_mut(segment)?,
+            };
+        }
+        Some(item)
+    }
+
+    /// replaces an item with an encrypted value, adding it at the top level
+    /// of the file or under the given table if it does not exist yet, values
+    /// that are already encrypted keep their attributes
+    pub fn encrypt(&mut self, path: Option<&str>, table: &[&str], key: &str, encrypted: String) {
+        let [settings, source] = env::ENCRYPTED;
+        if let Some(item) = path.and_then(|path| self.get_mut(path))
+            && Self::is_encrypted(item)
+        {
+            Self::replace(&mut item[settings][source], encrypted.into());
+            return;
+        }
+        let value = Self::spec([(source.to_string(), encrypted.into())]);
+        let item = match path.and_then(|path| self.get_mut(path)) {
+            Some(item) => item,
+            None => {
+                let mut item = self.document.as_item_mut();
+                for segment in table {
+                    item = &mut item[segment];
+                }
+                &mut item[key]
+            }
+        };
+        Self::replace(item, value);
+    }
+
+    /// replaces an encrypted value with its plain text, a value with
+    /// attributes like `secret` keeps them in its `envee` table
+    pub fn decrypt(&mut self, path: &str, plaintext: &str) {
+        let [settings, source] = env::ENCRYPTED;
+        let Some(item) = self.get_mut(path) else {
+            return;
+        };
+        let value = env::escape(plaintext);
+        let attributes: Vec<_> = item
+            .get(settings)
+            .and_then(Item::as_table_like)
+            .into_iter()
+            .flat_map(|settings| settings.iter())
+            .filter(|(key, _)| *key != source)
+            .filter_map(|(key, item)| Some((key.to_string(), item.as_value()?.clone())))
+            .collect();
+        if attributes.is_empty() {
+            Self::replace(item, value.into());
+            return;
+        }
+        let entries = [("value".to_string(), value.into())]
+            .into_iter()
+            .chain(attributes);
+        Self::replace(item, Self::spec(entries));
+    }
+
+    /// value with its source and attributes in its `envee` table, written
+    /// as dotted keys like `{ envee.value = "...", envee.secret = true }`
+    fn spec(entries: impl IntoIterator<Item = (String, Value)>) -> Value {
+        let mut settings = InlineTable::default();
+        settings.set_dotted(true);
+        for (key, value) in entries {
+            settings.insert(&key, value);
+        }
+        let mut table = InlineTable::default();
+        table.insert(env::ENCRYPTED[0], Value::InlineTable(settings));
+        Value::InlineTable(table)
+    }
+
+    /// replaces the value of an item keeping the comments around it
+    pub fn replace(item: &mut Item, mut value: Value) {
+        if let Some(old) = item.as_value() {
+            *value.decor_mut() = old.decor().clone();
+        }
+        *item = Item::Value(value);
+    }
+
+    /// dotted paths and values of every encrypted value in the file
+    pub fn encrypted(&self) -> Vec<(String, String)> {
+        let mut result = Vec::default();
+        Self::walk(self.document.as_item(), "", &mut result);
+        result
+    }
+
+    fn walk(item: &Item, path: &str, result: &mut Vec<(String, String)>) {
+        let join = |key: &str| match path.is_empty() {
+            true => key.to_string(),
+            false => format!("{path}.{key}"),
+        };
+        if let Some(table) = item.as_table_like() {
+            // the top level is never a value
+            if !path.is_empty()
+                && Self::is_encrypted(item)
+                && let Some(encrypted) = item[env::ENCRYPTED[0]][env::ENCRYPTED[1]].as_str()
+            {
+                result.push((path.to_string(), encrypted.to_string()));
+                return;
+            }
+            for (key, item) in table.iter() {
+                Self::walk(item, &join(key), result);
+            }
+        } else if let Some(tables) = item.as_array_of_tables() {
+            for (i, table) in tables.iter().enumerate() {
+                Self::walk(&Item::Table(table.clone()), &join(&i.to_string()), result);
+            }
+        } else if let Some(Value::Array(values)) = item.as_value() {
+            for (i, value) in values.iter().enumerate() {
+                Self::walk(&Item::Value(value.clone()), &join(&i.to_string()), result);
+            }
+        }
+    }
+
+    /// tables read as encrypted values by the resolver
+    fn is_encrypted(item: &Item) -> bool {
+        let [settings, source] = env::ENCRYPTED;
+        item.as_table_like().is_some_and(|table| table.len() == 1)
+            && item
+                .get(settings)
+                .and_then(Item::as_table_like)
+                .is_some_and(|settings| settings.contains_key(source))
+    }
+}
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::cli::document::Document;
+use crate::cli::{Secret, Task};
+use crate::env::Resolver;
+
+#[derive(Debug, Parser)]
+/// encrypt a value in an env file
+pub struct Encrypt {
+    /// toml file to edit
+    #[arg(short, long)]
+    file: PathBuf,
+
+    /// stage the variable is defined in, new variables are added to it
+    #[arg(short, long)]
+    stage: Option<String>,
+
+    #[command(flatten)]
+    secret: Secret,
+
+    /// value to encrypt instead of the current one
+    #[arg(long)]
+    value: Option<String>,
+
+    /// variable to encrypt
+    key: String,
+}
+
+impl Task for Encrypt {
+    fn run(&self) -> Result<ExitCode> {
+        let key = self.secret.required()?;
+        let file = fs::canonicalize(&self.file)
+            .with_context(|| format!("failed to read {}", self.file.display()))?;
+        let definition = Resolver::new(vec![file.clone()])
+            .stage(self.stage.clone())
+            .load()?
+            .into_iter()
+            .find(|definition| definition.key == self.key && definition.origin.file == file);
+        let value = match (&self.value, &definition) {
+            (Some(value), _) => value.clone(),
+            (None, Some(definition)) => definition
+                .source
+                .literal()
+                .with_context(|| format!("cannot encrypt {}", self.key))?,
+            (None, None) => anyhow::bail!("{} is not defined, pass its --value", self.key),
+        };
+        let table = match &self.stage {
+            Some(stage) => vec!["stages", stage.as_str()],
+            None => Vec::default(),
+        };
+        let path = definition.map(|definition| definition.origin.path);
+        // new variables are added to the table under their own name
+        let location = match &path {
+            Some(path) => path.clone(),
+            None => [&table[..], &[self.key.as_str()]].concat().join("."),
+        };
+        let mut document = Document::open(&file)?;
+        document.encrypt(
+            path.as_deref(),
+            &table,
+            &self.key,
+            key.encrypt(&location, &value)?,
+        );
+        document.save()?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -0,0 +1,87 @@
+use std::process::ExitCode;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+
+use crate::cli::{self, Source, Task};
+use crate::env::{self, Variable};
+use crate::redact::Redactor;
+
+#[derive(Debug, Parser)]
+/// show where a variable comes from
+pub struct Explain {
+    #[command(flatten)]
+    source: Source,
+
+    /// show the values of secrets instead of masking them
+    #[arg(long)]
+    reveal: bool,
+
+    /// variable to explain
+    key: String,
+}
+
+impl Task for Explain {
+    fn run(&self) -> Result<ExitCode> {
+        let variables = self.source.resolver()?.resolve()?;
+        cli::warn(&variables);
+        if !variables.iter().any(|variable| variable.key == self.key) {
+            bail!("variable not found: {}", self.key);
+        }
+        print!("{}", self.explain(&variables, &self.key, 0));
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+impl Explain {
+    /// the variable followed by every variable it references, indented by
+    /// how deep the reference is, with the secrets it references masked
+    fn explain(&self, variables: &[Variable], key: &str, depth: usize) -> String {
+        let indent = "  ".repeat(depth);
+        let Some(variable) = variables.iter().find(|variable| variable.key == key) else {
+            return Self::host(key, depth);
+        };
+        let masked = variable.secret && !self.reveal;
+        let redactor = cli::redactor(variables, variable, self.reveal);
+        let mut result = match masked {
+            true => format!("{indent}{key} = {} (secret)\n", Redactor::MASK),
+            false => format!(
+                "{indent}{key} = {:?}\n",
+                redactor.redact_str(&variable.value)
+            ),
+        };
+        let origin = &variable.origin;
+        result.push_str(&format!("{indent}  from {origin} ({})\n", origin.path));
+        match &variable.source {
+            env::Source::Template(template) if *template != variable.value && !masked => {
+                let template = redactor.redact_str(template);
+                result.push_str(&format!("{indent}  template {template:?}\n"));
+            }
+            env::Source::Template(_) => (),
+            env::Source::Provider(provider) => {
+                result.push_str(&format!("{indent}  provided by {provider}\n"));
+            }
+            env::Source::Encrypted(_) => result.push_str(&format!("{indent}  encrypted\n")),
+        }
+        for reference in &variable.references {
+            // a variable referencing itself extends what it replaces
+            match (reference == key, variable.overrides.last()) {
+                (true, Some(origin)) => {
+                    let indent = "  ".repeat(depth + 1);
+                    result.push_str(&format!("{indent}{key} defined earlier at {origin}\n"));
+                }
+                (true, None) => result.push_str(&Self::host(key, depth + 1)),
+                (false, _) => result.push_str(&self.explain(variables, reference, depth + 1)),
+            }
+        }
+        result
+    }
+
+    fn host(key: &str, depth: usize) -> String {
+        let indent = "  ".repeat(depth);
+        match std::env::var(key) {
+            Ok(value) => format!("{indent}{key} = {value:?} (host environment)\n"),
+            Err(_) => format!("{indent}{key} is not set\n"),
+        }
+    }
+}
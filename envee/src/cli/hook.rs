@@ -0,0 +1,249 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use serde_json::{Map, Value, json};
+
+use crate::cli::{Secret, Task};
+use crate::env::{self, Resolver};
+use crate::shell::Shell;
+use crate::trust::Trust;
+
+/// file loaded by the hook, found in the current directory or its parents
+pub const FILE: &str = "envee.toml";
+/// what the hook loaded, kept between prompts
+const STATE: &str = "ENVEE_LOADED";
+
+#[derive(Debug, Parser)]
+/// print a hook loading envee.toml when entering a directory, add it to
+/// your shell config with `eval "$(envee hook bash)"`
+pub struct Hook {
+    shell: Target,
+
+    /// print the commands loading and unloading the env of the current
+    /// directory, run by the hook before every prompt
+    #[arg(long, hide = true)]
+    apply: bool,
+
+    #[command(flatten)]
+    secret: Secret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Target {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// file the hook loaded and the values its variables replaced, `null` for
+/// variables that were not set
+#[derive(Debug, Default)]
+struct State {
+    file: PathBuf,
+    hash: String,
+    allowed: bool,
+    previous: Map<String, Value>,
+}
+
+impl Task for Hook {
+    fn run(&self) -> Result<ExitCode> {
+        match self.apply {
+            true => print!("{}", self.commands()?),
+            false => print!("{}", self.hook()?),
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+impl Hook {
+    fn hook(&self) -> Result<String> {
+        let exe = std::env::current_exe()?;
+        let exe = exe.to_string_lossy();
+        Ok(match self.shell {
+            Target::Bash => format!(
+                r#"_envee_hook() {{
+  local status=$?
+  eval "$({exe} hook bash --apply)"
+  return $status
+}}
+if [[ ";${{PROMPT_COMMAND[*]:-}};" != *";_envee_hook;"* ]]; then
+  PROMPT_COMMAND="_envee_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi
+"#,
+                exe = Shell::Posix.quote(&exe)
+            ),
+            Target::Zsh => format!(
+                r#"_envee_hook() {{
+  eval "$({exe} hook zsh --apply)"
+}}
+typeset -ag precmd_functions chpwd_functions
+if (( ! ${{precmd_functions[(I)_envee_hook]}} )); then
+  precmd_functions=(_envee_hook $precmd_functions)
+fi
+if (( ! ${{chpwd_functions[(I)_envee_hook]}} )); then
+  chpwd_functions=(_envee_hook $chpwd_functions)
+fi
+"#,
+                exe = Shell::Posix.quote(&exe)
+            ),
+            Target::Fish => format!(
+                r#"function __envee_hook --on-event fish_prompt --on-variable PWD
+    {exe} hook fish --apply | source
+end
+"#,
+                exe = Shell::Fish.quote(&exe)
+            ),
+        })
+    }
+
+    /// unloads what was loaded for another directory and loads the file of
+    /// this one, nothing happens while the same files stay unchanged
+    fn commands(&self) -> Result<String> {
+        let state = State::current();
+        let file = env::discover(&std::env::current_dir()?, FILE)
+            .map(fs::canonicalize)
+            .transpose()?;
+        let next = match file {
+            None => None,
+            Some(file) => {
+                let resolver = Resolver::new(vec![file.clone()]).key(self.secret.key()?);
+                // files that fail to parse are hashed on their own, to
+                // retry once they change
+                let paths = resolver.paths();
+                let hash = match &paths {
+                    Ok(paths) => Trust::hash(paths)?,
+                    Err(_) => Trust::hash(std::slice::from_ref(&file))?,
+                };
+                let allowed = Trust::load()?.allowed(&file, &hash);
+                if let Some(state) = &state
+                    && state.file == file
+                    && state.hash == hash
+                    && state.allowed == allowed
+                {
+                    return Ok(String::default());
+                }
+                let resolver = paths.map(|_| resolver);
+                Some((file, hash, allowed, resolver))
+            }
+        };
+        if state.is_none() && next.is_none() {
+            return Ok(String::default());
+        }
+        let state = state.unwrap_or_default();
+        let mut result = Vec::default();
+        let env = match &next {
+            None => Vec::default(),
+            Some((file, _, allowed, resolver)) => Self::load(file, *allowed, resolver),
+        };
+        for (key, value) in &state.previous {
+            if env.iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            result.push(match value {
+                Value::String(value) => self.set(key, value),
+                _ => self.unset(key),
+            });
+        }
+        if next.is_none() && !state.previous.is_empty() {
+            eprintln!("envee: unloading {}", state.file.display());
+        }
+        let Some((file, hash, allowed, _)) = next else {
+            result.push(self.unset(STATE));
+            return Ok(Self::lines(result));
+        };
+        let mut previous = Map::default();
+        for (key, value) in &env {
+            // values from before anything was loaded
+            let original = match state.previous.get(key) {
+                Some(original) => original.clone(),
+                None => std::env::var(key).map_or(Value::Null, Value::String),
+            };
+            previous.insert(key.clone(), original);
+            result.push(self.set(key, value));
+        }
+        let next = State {
+            file,
+            hash,
+            allowed,
+            previous,
+        };
+        result.push(self.set(STATE, &next.to_string()));
+        Ok(Self::lines(result))
+    }
+
+    /// variables of an allowed file, none if it is not allowed or fails to
+    /// resolve
+    fn load(file: &Path, allowed: bool, resolver: &Result<Resolver>) -> Vec<(String, String)> {
+        let resolver = match resolver {
+            Ok(resolver) => resolver,
+            Err(error) => {
+                eprintln!("envee: {error:#}");
+                return Vec::default();
+            }
+        };
+        if !allowed {
+            eprintln!(
+                "envee: {} is not allowed, run `envee allow` to load it",
+                file.display()
+            );
+            return Vec::default();
+        }
+        match resolver.get() {
+            Ok(env) => {
+                eprintln!("envee: loading {}", file.display());
+                env
+            }
+            Err(error) => {
+                eprintln!("envee: {error:#}");
+                Vec::default()
+            }
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> String {
+        match self.shell {
+            Target::Bash | Target::Zsh => format!("export {key}={}", Shell::Bash.quote(value)),
+            Target::Fish => format!("set -gx {key} {}", Shell::Fish.quote(value)),
+        }
+    }
+
+    fn unset(&self, key: &str) -> String {
+        match self.shell {
+            Target::Bash | Target::Zsh => format!("unset {key}"),
+            Target::Fish => format!("set -e {key}"),
+        }
+    }
+
+    fn lines(lines: Vec<String>) -> String {
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+}
+
+impl State {
+    /// state left by the hook, none if it did not run yet
+    fn current() -> Option<Self> {
+        let state: Value = serde_json::from_str(&std::env::var(STATE).ok()?).ok()?;
+        Some(Self {
+            file: PathBuf::from(state.get("file")?.as_str()?),
+            hash: state.get("hash")?.as_str()?.to_string(),
+            allowed: state.get("allowed")?.as_bool()?,
+            previous: state.get("previous")?.as_object()?.clone(),
+        })
+    }
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = json!({
+            "file": self.file.to_string_lossy(),
+            "hash": self.hash,
+            "allowed": self.allowed,
+            "previous": self.previous,
+        });
+        write!(f, "{state}")
+    }
+}
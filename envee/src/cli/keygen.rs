@@ -0,0 +1,37 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::cli::Task;
+use crate::crypto::Key;
+
+#[derive(Debug, Parser)]
+/// create a key file for encrypted values
+pub struct Keygen {
+    /// where to write the key, never overwritten
+    path: PathBuf,
+}
+
+impl Task for Keygen {
+    fn run(&self) -> Result<ExitCode> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        // only readable by the owner
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options
+            .open(&self.path)
+            .with_context(|| format!("failed to create {}", self.path.display()))?;
+        file.write_all(Key::generate()?.as_bytes())?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
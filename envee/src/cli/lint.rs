@@ -0,0 +1,154 @@
+use std::fmt::{self, Display, Formatter};
+use std::process::ExitCode;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use crate::cli::{Source, Task};
+use crate::env::{self, Definition, Diagnostic};
+
+#[derive(Debug, Parser)]
+/// find likely mistakes in env files, failing on errors
+pub struct Lint {
+    #[command(flatten)]
+    source: Source,
+
+    /// report these lints as errors
+    #[arg(short = 'D', long, value_delimiter = ',')]
+    deny: Vec<Check>,
+
+    /// report these lints as warnings
+    #[arg(short = 'W', long, value_delimiter = ',')]
+    warn: Vec<Check>,
+
+    /// do not report these lints, denying or warning takes precedence
+    #[arg(short = 'A', long, value_delimiter = ',')]
+    allow: Vec<Check>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Check {
+    /// references to variables defined nowhere, which expand to nothing
+    Undefined,
+    /// variables replacing one of the host environment
+    Shadowed,
+    /// definitions replaced by a later file with --override
+    Unused,
+    /// variables with an empty value
+    Empty,
+    /// keys with upper and lower case letters losing their case in the name
+    MixedCase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Allow,
+    Warning,
+    Error,
+}
+
+impl Task for Lint {
+    fn run(&self) -> Result<ExitCode> {
+        // nothing is resolved, so linting needs no key, runs no commands
+        // and does not stop at the first failing variable
+        let definitions = self.source.resolver()?.load()?;
+        let mut errors = 0;
+        let mut warnings = 0;
+        for (check, diagnostic) in Self::lint(&definitions)? {
+            let level = self.level(check);
+            match level {
+                Level::Allow => continue,
+                Level::Warning => warnings += 1,
+                Level::Error => errors += 1,
+            }
+            let name = check.to_possible_value().unwrap();
+            println!("{level}[{}]: {diagnostic}\n", name.get_name());
+        }
+        if errors + warnings > 0 {
+            println!(
+                "found {} and {}",
+                Self::count(errors, "error"),
+                Self::count(warnings, "warning")
+            );
+        }
+        Ok(match errors {
+            0 => ExitCode::SUCCESS,
+            _ => ExitCode::FAILURE,
+        })
+    }
+}
+
+impl Lint {
+    fn lint(definitions: &[Definition]) -> Result<Vec<(Check, Diagnostic)>> {
+        let mut result = Vec::default();
+        let values = env::expand(definitions);
+        for (definition, value) in definitions.iter().zip(values) {
+            let Definition { key, origin, .. } = definition;
+            for reference in definition.source.unguarded()? {
+                let defined = definitions.iter().any(|d| d.key == reference)
+                    || std::env::var_os(&reference).is_some();
+                if !defined {
+                    let message = format!("{key} references {reference}, which is not defined");
+                    result.push((Check::Undefined, Diagnostic::new(message).label(origin, "")));
+                }
+            }
+            if std::env::var_os(key).is_some() {
+                let message = format!("{key} shadows a variable of the host environment");
+                result.push((Check::Shadowed, Diagnostic::new(message).label(origin, "")));
+            }
+            for overridden in &definition.overrides {
+                let message = format!("{key} is overridden, this definition is never used");
+                let diagnostic = Diagnostic::new(message)
+                    .label(&overridden.origin, "never used")
+                    .label(origin, "overridden here");
+                result.push((Check::Unused, diagnostic));
+            }
+            if value.is_some_and(|value| value.is_empty()) {
+                let message = format!("{key} is empty");
+                result.push((Check::Empty, Diagnostic::new(message).label(origin, "")));
+            }
+            // dotenv keys are used as is, toml keys are the last part of the path
+            let name = origin.path.rsplit('.').next().unwrap_or_default();
+            let mixed = name.contains(|c: char| c.is_lowercase())
+                && name.contains(|c: char| c.is_uppercase());
+            if mixed && !key.contains(name) {
+                let message = format!("{name} has mixed case and becomes {key}");
+                result.push((Check::MixedCase, Diagnostic::new(message).label(origin, "")));
+            }
+        }
+        Ok(result)
+    }
+
+    fn level(&self, check: Check) -> Level {
+        if self.deny.contains(&check) {
+            return Level::Error;
+        }
+        if self.warn.contains(&check) {
+            return Level::Warning;
+        }
+        if self.allow.contains(&check) {
+            return Level::Allow;
+        }
+        match check {
+            Check::Undefined => Level::Error,
+            _ => Level::Warning,
+        }
+    }
+
+    fn count(n: usize, noun: &str) -> String {
+        match n {
+            1 => format!("1 {noun}"),
+            n => format!("{n} {noun}s"),
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
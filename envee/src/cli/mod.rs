@@ -1,11 +1,30 @@
+mod allow;
+mod check;
+mod decrypt;
+mod diff;
+mod document;
+mod encrypt;
+mod explain;
+mod hook;
+mod keygen;
+mod lint;
+mod rotate;
 mod run;
 mod show;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+
+use crate::crypto::Key;
+use crate::env::{Format, Resolver, Variable};
+use crate::project::Project;
+use crate::redact::Redactor;
 
 pub trait Task {
-    fn run(&self) -> Result<()>;
+    fn run(&self) -> Result<ExitCode>;
 }
 
 #[derive(Debug, Parser)]
@@ -17,7 +36,7 @@ pub struct Cli {
 }
 
 impl Task for Cli {
-    fn run(&self) -> Result<()> {
+    fn run(&self) -> Result<ExitCode> {
         self.command.run()
     }
 }
@@ -26,13 +45,149 @@ impl Task for Cli {
 pub enum Commands {
     Run(run::Run),
     Show(show::Show),
+    Explain(explain::Explain),
+    Check(check::Check),
+    Diff(diff::Diff),
+    Lint(lint::Lint),
+    Hook(hook::Hook),
+    Allow(allow::Allow),
+    Keygen(keygen::Keygen),
+    Encrypt(encrypt::Encrypt),
+    Decrypt(decrypt::Decrypt),
+    Rotate(rotate::Rotate),
 }
 
 impl Task for Commands {
-    fn run(&self) -> Result<()> {
+    fn run(&self) -> Result<ExitCode> {
         match self {
             Self::Run(task) => task.run(),
             Self::Show(task) => task.run(),
+            Self::Explain(task) => task.run(),
+            Self::Check(task) => task.run(),
+            Self::Diff(task) => task.run(),
+            Self::Lint(task) => task.run(),
+            Self::Hook(task) => task.run(),
+            Self::Allow(task) => task.run(),
+            Self::Keygen(task) => task.run(),
+            Self::Encrypt(task) => task.run(),
+            Self::Decrypt(task) => task.run(),
+            Self::Rotate(task) => task.run(),
+        }
+    }
+}
+
+/// warns about variables that replaced earlier definitions with
+/// --override, once per key
+fn warn<'a>(variables: impl IntoIterator<Item = &'a Variable>) {
+    let mut keys: Vec<&str> = Vec::default();
+    for variable in variables {
+        if !variable.overrides.is_empty() && !keys.contains(&variable.key.as_str()) {
+            keys.push(&variable.key);
+        }
+    }
+    if !keys.is_empty() {
+        eprintln!(
+            "warning: overridden environment variables: {}",
+            keys.join(", ")
+        );
+    }
+}
+
+/// masks the secrets a variable references, directly or through other
+/// variables, where its value includes them, nothing when they are revealed
+fn redactor(variables: &[Variable], variable: &Variable, reveal: bool) -> Redactor {
+    if reveal {
+        return Redactor::default();
+    }
+    let mut secrets = Vec::default();
+    let mut seen = vec![variable.key.as_str()];
+    let mut pending: Vec<&str> = variable.references.iter().map(String::as_str).collect();
+    while let Some(key) = pending.pop() {
+        if seen.contains(&key) {
+            continue;
         }
+        seen.push(key);
+        let Some(reference) = variables.iter().find(|variable| variable.key == key) else {
+            continue;
+        };
+        if reference.secret {
+            secrets.push(reference.value.as_str());
+        }
+        pending.extend(reference.references.iter().map(String::as_str));
+    }
+    Redactor::new(secrets)
+}
+
+#[derive(Debug, Args)]
+struct Source {
+    /// path(s) to your env file(s), defaults to the files of the project
+    /// config found in the current directory or its parents
+    #[arg(short, long)]
+    files: Vec<PathBuf>,
+
+    /// stage to layer over the shared keys
+    #[arg(short, long)]
+    stage: Option<String>,
+
+    /// let later files override earlier ones
+    #[arg(long = "override")]
+    overrides: bool,
+
+    /// parse files with this format instead of detecting it from their name
+    #[arg(long)]
+    input_format: Option<Format>,
+
+    /// schema to validate the env against, its rules take precedence over
+    /// the `schema` tables of the env files
+    #[arg(long)]
+    schema: Option<PathBuf>,
+
+    #[command(flatten)]
+    secret: Secret,
+}
+
+impl Source {
+    fn resolver(&self) -> Result<Resolver> {
+        self.with(&self.files, self.stage.as_ref())
+    }
+
+    /// resolver for other files or another stage, sharing every other option,
+    /// anything not passed falls back to the project config
+    fn with(&self, files: &[PathBuf], stage: Option<&String>) -> Result<Resolver> {
+        let project = Project::current()?.unwrap_or_default();
+        // the stage of the project only applies to its own files
+        let (files, stage) = match files.is_empty() {
+            true => (project.files, stage.cloned().or(project.stage)),
+            false => (files.to_vec(), stage.cloned()),
+        };
+        Ok(Resolver::new(files)
+            .stage(stage)
+            .overrides(self.overrides || project.overrides)
+            .format(self.input_format)
+            .schema(self.schema.clone().or(project.schema))
+            .key(self.secret.key()?))
+    }
+}
+
+#[derive(Debug, Args)]
+struct Secret {
+    /// key file for encrypted values, defaults to a key derived from the
+    /// passphrase in ENVEE_PASSPHRASE
+    #[arg(long, env = "ENVEE_KEY_FILE")]
+    key_file: Option<PathBuf>,
+}
+
+impl Secret {
+    fn key(&self) -> Result<Option<Key>> {
+        let file = match &self.key_file {
+            Some(file) => Some(file.clone()),
+            None => Project::current()?.and_then(|project| project.key_file),
+        };
+        Key::load(file.as_deref(), "ENVEE_PASSPHRASE")
+    }
+
+    fn required(&self) -> Result<Key> {
+        self.key()?
+            .context("no key, pass --key-file or set ENVEE_KEY_FILE or ENVEE_PASSPHRASE")
     }
 }
@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::cli::document::Document;
+use crate::cli::{Secret, Task};
+use crate::crypto::Key;
+
+#[derive(Debug, Parser)]
+/// re-encrypt every encrypted value in an env file with a new key
+pub struct Rotate {
+    /// toml file to edit
+    #[arg(short, long)]
+    file: PathBuf,
+
+    #[command(flatten)]
+    secret: Secret,
+
+    /// new key file, defaults to a key derived from the passphrase in
+    /// ENVEE_NEW_PASSPHRASE
+    #[arg(long)]
+    new_key_file: Option<PathBuf>,
+}
+
+impl Task for Rotate {
+    fn run(&self) -> Result<ExitCode> {
+        let key = self.secret.required()?;
+        let new = Key::load(self.new_key_file.as_deref(), "ENVEE_NEW_PASSPHRASE")?
+            .context("no new key, pass --new-key-file or set ENVEE_NEW_PASSPHRASE")?;
+        let mut document = Document::open(&self.file)?;
+        for (path, encrypted) in document.encrypted() {
+            let plaintext = key
+                .decrypt(&path, &encrypted)
+                .with_context(|| format!("failed to decrypt {path}"))?;
+            document.encrypt(Some(&path), &[], "", new.encrypt(&path, &plaintext)?);
+        }
+        document.save()?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -1,17 +1,53 @@
-use std::path::PathBuf;
-use std::process::Command;
+use std::fs;
+use std::io;
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitCode, ExitStatus, Stdio};
+use std::thread::{self, JoinHandle};
+#[cfg(unix)]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
 
-use anyhow::{Result, bail};
+use anyhow::Result;
 use clap::Parser;
+#[cfg(unix)]
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::{SignalsInfo, exfiltrator::WithOrigin},
+};
 
-use crate::{cli::Task, env::Resolver};
+use crate::cli::{self, Source, Task};
+use crate::env::{Resolver, Variable};
+use crate::pattern::Pattern;
+use crate::redact::Redactor;
 
 #[derive(Debug, Parser)]
 /// inject env at runtime
 pub struct Run {
-    /// path(s) to your env file(s)
-    #[arg(short, long)]
-    files: Vec<PathBuf>,
+    #[command(flatten)]
+    source: Source,
+
+    /// start from an empty environment instead of inheriting the current one
+    #[arg(long)]
+    isolated: bool,
+
+    /// variables to pass through when isolated, supports `*` and `?` globs
+    #[arg(long, requires = "isolated", value_delimiter = ',')]
+    inherit: Vec<Pattern>,
+
+    /// replace envee with the command instead of running it as a child (unix only)
+    #[arg(long)]
+    exec: bool,
+
+    /// replace the values of secrets in the output of the command with `****`
+    #[arg(long, conflicts_with = "exec")]
+    redact: bool,
+
+    /// restart the command whenever the env changes, the files it is read
+    /// from are watched (unix only)
+    #[arg(long, conflicts_with = "exec")]
+    watch: bool,
 
     /// command to run in environment
     #[arg(required = true, last = true)]
@@ -19,14 +55,302 @@ pub struct Run {
 }
 
 impl Task for Run {
-    fn run(&self) -> Result<()> {
-        let program = &self.args[0];
-        let args = &self.args[1..];
-        let env = Resolver::new(self.files.clone()).get()?;
-        let status = Command::new(program).args(args).envs(env).status()?;
-        if !status.success() {
-            bail!("command failed: {status}")
+    fn run(&self) -> Result<ExitCode> {
+        let resolver = self.source.resolver()?;
+        let variables = resolver.resolve()?;
+        cli::warn(&variables);
+        if self.exec {
+            return Self::exec(self.command(&variables));
+        }
+        if self.watch {
+            return self.watch(&resolver, variables);
         }
+        let status = Self::wait(self.command(&variables), self.redactor(&variables))?;
+        Ok(Self::code(status))
+    }
+}
+
+/// child along with the threads copying its redacted output
+type Running = (Child, Vec<JoinHandle<io::Result<()>>>);
+
+impl Run {
+    /// how often files and the child are checked when watching
+    const POLL: Duration = Duration::from_millis(100);
+    /// how long a child may take to exit before it is killed on restart
+    const GRACE: Duration = Duration::from_secs(5);
+
+    fn command(&self, variables: &[Variable]) -> Command {
+        let mut command = Command::new(&self.args[0]);
+        if self.isolated {
+            command.env_clear();
+            command.envs(std::env::vars_os().filter(|(key, _)| {
+                let key = key.to_str().unwrap_or_default();
+                self.inherit.iter().any(|pattern| pattern.matches(key))
+            }));
+        }
+        // the passphrases only unlock values for envee itself
+        command
+            .env_remove("ENVEE_PASSPHRASE")
+            .env_remove("ENVEE_NEW_PASSPHRASE");
+        command.args(&self.args[1..]).envs(
+            variables
+                .iter()
+                .map(|variable| (&variable.key, &variable.value)),
+        );
+        if self.redact {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+        command
+    }
+
+    fn redactor(&self, variables: &[Variable]) -> Option<Redactor> {
+        let secrets = variables
+            .iter()
+            .filter(|variable| variable.secret)
+            .map(|variable| &variable.value);
+        self.redact.then(|| Redactor::new(secrets))
+    }
+
+    #[cfg(unix)]
+    fn exec(mut command: Command) -> Result<ExitCode> {
+        // only returns if the command could not be executed
+        Err(command.exec().into())
+    }
+
+    #[cfg(not(unix))]
+    fn exec(_: Command) -> Result<ExitCode> {
+        anyhow::bail!("exec is only supported on unix")
+    }
+
+    /// signals sent to envee are forwarded to the child, the ones coming from
+    /// the terminal already reach the whole process group so they are skipped
+    #[cfg(unix)]
+    fn wait(mut command: Command, redactor: Option<Redactor>) -> Result<ExitStatus> {
+        // registered before spawning so no signal can kill envee early
+        let mut signals = SignalsInfo::<WithOrigin>::new([SIGINT, SIGTERM, SIGHUP])?;
+        let handle = signals.handle();
+        let mut child = command.spawn()?;
+        let copies = Self::redact(&mut child, redactor);
+        let pid = child.id() as libc::pid_t;
+        let forward = thread::spawn(move || {
+            for origin in signals.forever() {
+                if origin.process.is_some() {
+                    unsafe { libc::kill(pid, origin.signal) };
+                }
+            }
+        });
+        let status = child.wait();
+        handle.close();
+        forward.join().unwrap();
+        Self::join(copies);
+        Ok(status?)
+    }
+
+    #[cfg(not(unix))]
+    fn wait(mut command: Command, redactor: Option<Redactor>) -> Result<ExitStatus> {
+        let mut child = command.spawn()?;
+        let copies = Self::redact(&mut child, redactor);
+        let status = child.wait();
+        Self::join(copies);
+        Ok(status?)
+    }
+
+    /// runs the child until envee is told to stop, restarting it whenever
+    /// the env changes, a child that exits is started again on the next change
+    #[cfg(unix)]
+    fn watch(&self, resolver: &Resolver, mut variables: Vec<Variable>) -> Result<ExitCode> {
+        let mut signals = SignalsInfo::<WithOrigin>::new([SIGINT, SIGTERM, SIGHUP])?;
+        let mut stamps = Stamps::new(resolver.paths()?);
+        let mut child = Some(self.spawn(&variables)?);
+        let mut settling = false;
+        loop {
+            thread::sleep(Self::POLL);
+            // forwarded like without watching, terminal signals already
+            // reached the child
+            if let Some(origin) = signals.pending().next() {
+                let Some((mut running, copies)) = child else {
+                    return Ok(ExitCode::from(128 + origin.signal as u8));
+                };
+                if origin.process.is_some() {
+                    unsafe { libc::kill(running.id() as libc::pid_t, origin.signal) };
+                }
+                let status = running.wait()?;
+                Self::join(copies);
+                return Ok(Self::code(status));
+            }
+            if let Some((running, _)) = &mut child
+                && let Some(status) = running.try_wait()?
+            {
+                let program = &self.args[0];
+                eprintln!("envee: {program} exited with {status}, waiting for changes");
+                Self::join(child.take().unwrap().1);
+            }
+            // files are read once they stopped changing, not halfway through
+            // being written
+            if stamps.changed() {
+                settling = true;
+                continue;
+            }
+            if !std::mem::take(&mut settling) {
+                continue;
+            }
+            // extended files may have been added or removed
+            let next = resolver
+                .paths()
+                .and_then(|paths| Ok((paths, resolver.resolve()?)));
+            let next = match next {
+                Ok((paths, next)) => {
+                    stamps = Stamps::new(paths);
+                    next
+                }
+                Err(error) => {
+                    eprintln!("envee: not restarting, {error:#}");
+                    continue;
+                }
+            };
+            let changes = Self::changes(&variables, &next);
+            if changes.is_empty() && child.is_some() {
+                continue;
+            }
+            match changes.is_empty() {
+                true => eprintln!("envee: restarting"),
+                false => eprintln!("envee: restarting, changed {}", changes.join(", ")),
+            }
+            if let Some(running) = child.take() {
+                Self::stop(running)?;
+            }
+            variables = next;
+            // started again on the next change
+            match self.spawn(&variables) {
+                Ok(running) => child = Some(running),
+                Err(error) => {
+                    let program = &self.args[0];
+                    eprintln!("envee: failed to restart {program}, {error:#}");
+                }
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn watch(&self, _: &Resolver, _: Vec<Variable>) -> Result<ExitCode> {
+        anyhow::bail!("watch is only supported on unix")
+    }
+
+    fn spawn(&self, variables: &[Variable]) -> Result<Running> {
+        let mut child = self.command(variables).spawn()?;
+        let copies = Self::redact(&mut child, self.redactor(variables));
+        Ok((child, copies))
+    }
+
+    /// asks the child to terminate, killing it once the grace period passes
+    #[cfg(unix)]
+    fn stop((mut child, copies): Running) -> Result<()> {
+        unsafe { libc::kill(child.id() as libc::pid_t, SIGTERM) };
+        let start = Instant::now();
+        while child.try_wait()?.is_none() {
+            if start.elapsed() > Self::GRACE {
+                child.kill()?;
+                child.wait()?;
+                break;
+            }
+            thread::sleep(Self::POLL);
+        }
+        Self::join(copies);
         Ok(())
     }
+
+    /// keys that were added, removed or changed, marked with `+`, `-` or `~`
+    fn changes(old: &[Variable], new: &[Variable]) -> Vec<String> {
+        let mut result = Vec::default();
+        for variable in old {
+            match new.iter().find(|v| v.key == variable.key) {
+                None => result.push(format!("-{}", variable.key)),
+                Some(v) if v.value != variable.value => result.push(format!("~{}", v.key)),
+                Some(_) => (),
+            }
+        }
+        for variable in new {
+            if !old.iter().any(|v| v.key == variable.key) {
+                result.push(format!("+{}", variable.key));
+            }
+        }
+        result
+    }
+
+    /// copies the piped output of the child with secrets replaced, each
+    /// stream on its own thread so neither pipe fills up
+    fn redact(child: &mut Child, redactor: Option<Redactor>) -> Vec<JoinHandle<io::Result<()>>> {
+        let Some(redactor) = redactor else {
+            return Vec::default();
+        };
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        let other = redactor.clone();
+        vec![
+            thread::spawn(move || redactor.copy(stdout, io::stdout())),
+            thread::spawn(move || other.copy(stderr, io::stderr())),
+        ]
+    }
+
+    /// failing to write, like to a closed pipe, closes the pipe of the child
+    /// as well so it is not reported
+    fn join(copies: Vec<JoinHandle<io::Result<()>>>) {
+        for copy in copies {
+            let _ = copy.join().unwrap();
+        }
+    }
+
+    /// exit code of the child, a child killed by a signal follows the shell
+    /// convention of 128 plus the signal number
+    fn code(status: ExitStatus) -> ExitCode {
+        #[cfg(unix)]
+        if let Some(signal) = status.signal() {
+            return ExitCode::from(128 + signal as u8);
+        }
+        // codes that do not fit, like windows status codes, would wrap
+        // around and could turn a failure into success
+        match status.code().map(u8::try_from) {
+            Some(Ok(code)) => ExitCode::from(code),
+            _ => ExitCode::FAILURE,
+        }
+    }
+}
+
+/// modification time and size of every watched file, none for files that
+/// do not exist
+#[derive(Debug)]
+struct Stamps {
+    files: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+}
+
+impl Stamps {
+    fn new(paths: Vec<PathBuf>) -> Self {
+        Self {
+            files: paths
+                .into_iter()
+                .map(|path| {
+                    let stamp = Self::stamp(&path);
+                    (path, stamp)
+                })
+                .collect(),
+        }
+    }
+
+    fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// whether any file changed since the last call
+    fn changed(&mut self) -> bool {
+        let mut result = false;
+        for (path, stamp) in &mut self.files {
+            let next = Self::stamp(path);
+            if next != *stamp {
+                *stamp = next;
+                result = true;
+            }
+        }
+        result
+    }
 }
@@ -1,24 +1,62 @@
-use std::path::PathBuf;
+use std::process::ExitCode;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Result, bail};
+use clap::{Parser, ValueEnum};
 
-use crate::{cli::Task, env::Resolver};
+use crate::cli::{self, Source, Task};
+use crate::output::Format;
+use crate::redact::Redactor;
 
 #[derive(Debug, Parser)]
 /// show final env
 pub struct Show {
-    /// path(s) to your env file(s)
-    #[arg(short, long)]
-    files: Vec<PathBuf>,
+    #[command(flatten)]
+    source: Source,
+
+    /// format to print the env in
+    #[arg(long, value_enum, default_value_t = Format::Export)]
+    format: Format,
+
+    /// name of the kubernetes configmap or secret
+    #[arg(long, default_value = "envee")]
+    name: String,
+
+    /// show the values of secrets instead of masking them
+    #[arg(long)]
+    reveal: bool,
 }
 
 impl Task for Show {
-    fn run(&self) -> Result<()> {
-        let env = Resolver::new(self.files.clone()).get()?;
-        for (key, value) in env {
-            println!("export {key}=\"{value}\"");
+    fn run(&self) -> Result<ExitCode> {
+        let variables = self.source.resolver()?.resolve()?;
+        cli::warn(&variables);
+        let masked: Vec<_> = variables
+            .iter()
+            .filter(|variable| variable.secret && !self.reveal)
+            .map(|variable| variable.key.as_str())
+            .collect();
+        if !masked.is_empty() {
+            let masked = masked.join(", ");
+            if !self.format.readable() {
+                let format = self.format.to_possible_value().unwrap();
+                bail!(
+                    "secrets cannot be masked in {} output, pass --reveal to include them: {masked}",
+                    format.get_name()
+                );
+            }
+            eprintln!("warning: masked secrets: {masked}, pass --reveal to show them");
         }
-        Ok(())
+        let env: Vec<_> = variables
+            .iter()
+            .map(|variable| match variable.secret && !self.reveal {
+                true => (variable.key.clone(), Redactor::MASK.to_string()),
+                false => {
+                    let redactor = cli::redactor(&variables, variable, self.reveal);
+                    (variable.key.clone(), redactor.redact_str(&variable.value))
+                }
+            })
+            .collect();
+        print!("{}", self.format.render(&env, &self.name)?);
+        Ok(ExitCode::SUCCESS)
     }
 }
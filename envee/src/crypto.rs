@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::Argon2;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+/// key used to encrypt values at rest, either random bytes kept in a key
+/// file or derived from a passphrase
+///
+/// encrypted values are base64 of a version byte, the kind of key, the salt
+/// for passphrases, the nonce and the authenticated ciphertext, the dotted
+/// path of the value in its file is authenticated along with it so values
+/// cannot be swapped between keys
+pub struct Key {
+    secret: Secret,
+    /// salt used for every value encrypted with a passphrase
+    salt: [u8; Self::SALT],
+    /// derived keys by salt, deriving is deliberately slow
+    derived: RefCell<HashMap<[u8; Self::SALT], [u8; Self::SIZE]>>,
+}
+
+enum Secret {
+    Bytes([u8; Key::SIZE]),
+    Passphrase(String),
+}
+
+impl Key {
+    const VERSION: u8 = 1;
+    const SIZE: usize = 32;
+    const SALT: usize = 16;
+    const NONCE: usize = 24;
+    /// marks values encrypted with a key file
+    const BYTES: u8 = 0;
+    /// marks values encrypted with a passphrase
+    const PASSPHRASE: u8 = 1;
+
+    /// the key in a file, otherwise one derived from the passphrase in the
+    /// variable named by `passphrase`
+    pub fn load(file: Option<&Path>, passphrase: &str) -> Result<Option<Self>> {
+        if let Some(file) = file {
+            let text = fs::read_to_string(file)
+                .with_context(|| format!("failed to read key file {}", file.display()))?;
+            let bytes = STANDARD
+                .decode(text.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .with_context(|| format!("invalid key file {}", file.display()))?;
+            return Ok(Some(Self::new(Secret::Bytes(bytes))?));
+        }
+        match std::env::var(passphrase) {
+            Ok(passphrase) if !passphrase.is_empty() => {
+                Ok(Some(Self::new(Secret::Passphrase(passphrase))?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// contents of a new key file
+    pub fn generate() -> Result<String> {
+        let bytes: [u8; Self::SIZE] = Self::random()?;
+        Ok(format!("{}\n", STANDARD.encode(bytes)))
+    }
+
+    fn new(secret: Secret) -> Result<Self> {
+        Ok(Self {
+            secret,
+            salt: Self::random()?,
+            derived: RefCell::default(),
+        })
+    }
+
+    pub fn encrypt(&self, path: &str, plaintext: &str) -> Result<String> {
+        let nonce: [u8; Self::NONCE] = Self::random()?;
+        let mut result = vec![Self::VERSION];
+        let cipher = match &self.secret {
+            Secret::Bytes(bytes) => {
+                result.push(Self::BYTES);
+                Self::cipher(bytes)
+            }
+            Secret::Passphrase(_) => {
+                result.push(Self::PASSPHRASE);
+                result.extend(self.salt);
+                Self::cipher(&self.derive(self.salt)?)
+            }
+        };
+        let ciphertext = cipher
+            .encrypt(
+                &XNonce::from(nonce),
+                Self::payload(path, plaintext.as_bytes()),
+            )
+            .map_err(|_| anyhow!("failed to encrypt"))?;
+        result.extend(nonce);
+        result.extend(ciphertext);
+        Ok(STANDARD.encode(result))
+    }
+
+    pub fn decrypt(&self, path: &str, encrypted: &str) -> Result<String> {
+        let data = STANDARD
+            .decode(encrypted.trim())
+            .ok()
+            .filter(|data| data.len() > 2 && data[0] == Self::VERSION)
+            .context("not an encrypted value")?;
+        let (cipher, rest) = match (data[1], &self.secret) {
+            (Self::BYTES, Secret::Bytes(bytes)) => (Self::cipher(bytes), &data[2..]),
+            (Self::PASSPHRASE, Secret::Passphrase(_)) if data.len() > 2 + Self::SALT => {
+                let (salt, rest) = data[2..].split_at(Self::SALT);
+                let key = self.derive(salt.try_into().unwrap())?;
+                (Self::cipher(&key), rest)
+            }
+            (Self::BYTES, _) => bail!("value was encrypted with a key file"),
+            (Self::PASSPHRASE, _) => bail!("value was encrypted with a passphrase"),
+            _ => bail!("not an encrypted value"),
+        };
+        if rest.len() < Self::NONCE {
+            bail!("not an encrypted value");
+        }
+        let (nonce, ciphertext) = rest.split_at(Self::NONCE);
+        let nonce: [u8; Self::NONCE] = nonce.try_into().unwrap();
+        let plaintext = cipher
+            .decrypt(&XNonce::from(nonce), Self::payload(path, ciphertext))
+            .map_err(|_| {
+                anyhow!("failed to decrypt, the key is wrong or the value was changed or moved")
+            })?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    fn derive(&self, salt: [u8; Self::SALT]) -> Result<[u8; Self::SIZE]> {
+        let Secret::Passphrase(passphrase) = &self.secret else {
+            unreachable!();
+        };
+        if let Some(key) = self.derived.borrow().get(&salt) {
+            return Ok(*key);
+        }
+        let mut key = [0; Self::SIZE];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|error| anyhow!("failed to derive key: {error}"))?;
+        self.derived.borrow_mut().insert(salt, key);
+        Ok(key)
+    }
+
+    fn payload<'a>(path: &'a str, msg: &'a [u8]) -> Payload<'a, 'a> {
+        Payload {
+            msg,
+            aad: path.as_bytes(),
+        }
+    }
+
+    fn cipher(key: &[u8; Self::SIZE]) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&(*key).into())
+    }
+
+    fn random<const N: usize>() -> Result<[u8; N]> {
+        let mut result = [0; N];
+        getrandom::fill(&mut result).map_err(|error| anyhow!("{error}"))?;
+        Ok(result)
+    }
+}
+
+/// never shows the secret
+impl Debug for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Key").finish_non_exhaustive()
+    }
+}
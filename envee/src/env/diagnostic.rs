@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+
+use super::Origin;
+
+/// error pointing at the definitions that caused it, rendered with the
+/// offending lines and a caret under the key
+#[derive(Debug)]
+pub struct Diagnostic {
+    message: String,
+    labels: Vec<Label>,
+}
+
+#[derive(Debug)]
+struct Label {
+    origin: Origin,
+    note: String,
+    /// line the origin points at
+    source: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            labels: Vec::default(),
+        }
+    }
+
+    /// the line is read right away, the file may be gone by the time the
+    /// error is shown
+    pub fn label(mut self, origin: &Origin, note: impl Into<String>) -> Self {
+        let source = origin.position.and_then(|(line, _)| {
+            let text = fs::read_to_string(&origin.file).ok()?;
+            text.lines().nth(line - 1).map(str::to_string)
+        });
+        self.labels.push(Label {
+            origin: origin.clone(),
+            note: note.into(),
+            source,
+        });
+        self
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for Label {
+            origin,
+            note,
+            source,
+        } in &self.labels
+        {
+            write!(f, "\n  --> {origin}")?;
+            if !origin.path.is_empty() {
+                write!(f, " ({})", origin.path)?;
+            }
+            let note = match note.is_empty() {
+                true => String::default(),
+                false => format!(" {note}"),
+            };
+            let (Some((line, column)), Some(source)) = (origin.position, source) else {
+                write!(f, "{note}")?;
+                continue;
+            };
+            let number = line.to_string();
+            let gutter = " ".repeat(number.len());
+            write!(f, "\n {gutter} |")?;
+            write!(f, "\n {number} | {source}")?;
+            let padding: String = source
+                .chars()
+                .take(column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, "\n {gutter} | {padding}^{note}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Diagnostic {}
+
+/// error at a position in the text being parsed, turned into a diagnostic
+/// once the file it came from is known
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub position: Option<(usize, usize)>,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, position: Option<(usize, usize)>) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::diagnostic::ParseError;
+use super::{Definition, Origin, Source};
+
+/// `KEY=value` lines as written by most tools, values are stored as
+/// templates so the `$` in single quoted and escaped values is doubled
+#[derive(Debug)]
+pub struct Dotenv {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    value: String,
+    position: (usize, usize),
+}
+
+impl Dotenv {
+    pub fn new(text: &str) -> Result<Self> {
+        let mut parser = Parser { text, pos: 0 };
+        let mut entries = Vec::default();
+        while let Some(entry) = parser.entry()? {
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn env(&self, file: &Path) -> Vec<Definition> {
+        let secrets = super::secrets();
+        self.entries
+            .iter()
+            .map(|entry| Definition {
+                key: entry.key.clone(),
+                source: Source::Template(entry.value.clone()),
+                origin: Origin {
+                    file: file.to_path_buf(),
+                    path: entry.key.clone(),
+                    position: Some(entry.position),
+                },
+                secret: secrets.iter().any(|pattern| pattern.matches(&entry.key)),
+                overrides: Vec::default(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn entry(&mut self) -> Result<Option<Entry>> {
+        loop {
+            self.skip(|c| c.is_whitespace());
+            match self.peek() {
+                None => return Ok(None),
+                Some('#') => self.skip(|c| c != '\n'),
+                Some(_) => break,
+            }
+        }
+        let mut position = Origin::position(self.text, self.pos);
+        let mut key = self.take(|c| c == '_' || c == '.' || c == '-' || c.is_alphanumeric());
+        if key == "export" && self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+            self.skip(|c| c == ' ' || c == '\t');
+            position = Origin::position(self.text, self.pos);
+            key = self.take(|c| c == '_' || c == '.' || c == '-' || c.is_alphanumeric());
+        }
+        if key.is_empty() {
+            return Err(self.error(self.pos, "expected a key"));
+        }
+        self.skip(|c| c == ' ' || c == '\t');
+        if self.peek() != Some('=') {
+            return Err(self.error(self.pos, format!("expected '=' after {key}")));
+        }
+        self.bump();
+        self.skip(|c| c == ' ' || c == '\t');
+        let value = match self.peek() {
+            Some('\'') => self.single()?,
+            Some('"') => self.double()?,
+            _ => self.unquoted(),
+        };
+        self.skip(|c| c == ' ' || c == '\t');
+        match self.peek() {
+            None | Some('\n') => (),
+            Some('#') => self.skip(|c| c != '\n'),
+            Some(_) => {
+                let message = format!("unexpected text after value of {key}");
+                return Err(self.error(self.pos, message));
+            }
+        }
+        Ok(Some(Entry {
+            key,
+            value,
+            position,
+        }))
+    }
+
+    /// literal up to the closing quote, no escapes or expansion
+    fn single(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.bump();
+        let value = self.take(|c| c != '\'');
+        if self.peek().is_none() {
+            return Err(self.error(start, "unterminated single quoted value"));
+        }
+        self.bump();
+        Ok(super::escape(&value))
+    }
+
+    /// supports escape sequences and expansion
+    fn double(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.bump();
+        let mut result = String::default();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error(start, "unterminated double quoted value"));
+            };
+            self.bump();
+            match c {
+                '"' => break,
+                '\\' => {
+                    let Some(c) = self.peek() else {
+                        continue;
+                    };
+                    self.bump();
+                    match c {
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        '$' => result.push_str("$$"),
+                        '"' | '\\' | '`' => result.push(c),
+                        // a backslash before a newline continues the line
+                        '\n' => (),
+                        _ => {
+                            result.push('\\');
+                            result.push(c);
+                        }
+                    }
+                }
+                _ => result.push(c),
+            }
+        }
+        Ok(result)
+    }
+
+    /// rest of the line up to an inline comment, surrounding space removed
+    fn unquoted(&mut self) -> String {
+        let mut result = String::default();
+        while let Some(c) = self.peek() {
+            if c == '\n' || (c == '#' && result.ends_with([' ', '\t'])) {
+                break;
+            }
+            result.push(c);
+            self.bump();
+        }
+        result.trim().to_string()
+    }
+
+    fn take<F>(&mut self, f: F) -> String
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.pos;
+        self.skip(f);
+        self.text[start..self.pos].to_string()
+    }
+
+    fn skip<F>(&mut self, f: F)
+    where
+        F: Fn(char) -> bool,
+    {
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, offset: usize, message: impl Into<String>) -> anyhow::Error {
+        let position = Origin::position(self.text, offset);
+        ParseError::new(message, Some(position)).into()
+    }
+}
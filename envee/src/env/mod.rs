@@ -0,0 +1,562 @@
+mod diagnostic;
+mod dotenv;
+mod provider;
+mod schema;
+mod template;
+mod toml;
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+
+use crate::crypto::Key;
+use crate::pattern::Pattern;
+
+pub use diagnostic::Diagnostic;
+use diagnostic::ParseError;
+use dotenv::Dotenv;
+pub use provider::Provider;
+use schema::Schema;
+pub use schema::Violations;
+use template::Template;
+pub use toml::ENCRYPTED;
+use toml::Toml;
+
+type Env = Vec<(String, String)>;
+type Current = HashMap<String, String>;
+
+/// where a variable was defined
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub file: PathBuf,
+    /// dotted path of the key within the file
+    pub path: String,
+    /// line and column of the key, for formats that keep track of them
+    pub position: Option<(usize, usize)>,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Origin {
+    /// 1 based line and column of a byte offset into text
+    fn position(text: &str, offset: usize) -> (usize, usize) {
+        let before = &text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap().chars().count() + 1;
+        (line, column)
+    }
+}
+
+/// how the value of a variable is produced
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// expanded with the values of the variables it references
+    Template(String),
+    /// looked up when resolving and used verbatim
+    Provider(Provider),
+    /// decrypted with the key of the resolver and used verbatim
+    Encrypted(String),
+}
+
+/// names of variables treated as secret, unless a file sets its own
+const SECRETS: &[&str] = &["*_TOKEN", "*_SECRET", "*_PASSWORD", "*_KEY"];
+
+fn secrets() -> Vec<Pattern> {
+    SECRETS
+        .iter()
+        .map(|pattern| pattern.parse().unwrap())
+        .collect()
+}
+
+/// first file with this name in the directory or any of its parents
+pub fn discover(dir: &Path, name: &str) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// whether a shell can use the name, letters, digits and underscores only
+/// and not starting with a digit
+fn valid(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// values of the definitions as far as they are known without running
+/// providers or decrypting, none for those and for templates that depend on
+/// them or fail to expand
+pub fn expand(definitions: &[Definition]) -> Vec<Option<String>> {
+    let mut expander = Expander::new(definitions, None);
+    expander.opaque = true;
+    definitions
+        .iter()
+        .map(|definition| {
+            // failures leave the keys they were expanding behind
+            expander.stack.clear();
+            expander.resolve(&definition.key).ok().flatten()
+        })
+        .collect()
+}
+
+/// template expanding to the value as is
+pub fn escape(value: &str) -> String {
+    value.replace('$', "$$")
+}
+
+impl Source {
+    /// value of a template that does not reference other variables
+    pub fn literal(&self) -> Result<String> {
+        match self {
+            Self::Template(template) => Template::parse(template)?
+                .render(&mut |name| bail!("references {name}, only literal values are supported")),
+            Self::Provider(provider) => bail!("provided by {provider}"),
+            Self::Encrypted(_) => bail!("already encrypted"),
+        }
+    }
+
+    /// variables a template references without an operator, these expand
+    /// to nothing when not set
+    pub fn unguarded(&self) -> Result<Vec<String>> {
+        match self {
+            Self::Template(template) => {
+                let template = Template::parse(template)?;
+                Ok(template
+                    .unguarded()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect())
+            }
+            Self::Provider(_) | Self::Encrypted(_) => Ok(Vec::default()),
+        }
+    }
+}
+
+/// unexpanded variable as it was read from a file
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub key: String,
+    pub source: Source,
+    pub origin: Origin,
+    /// value is masked when shown
+    pub secret: bool,
+    /// earlier definitions this one replaced with --override, oldest first
+    pub overrides: Vec<Definition>,
+}
+
+/// fully resolved variable along with where its value came from
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub key: String,
+    pub value: String,
+    /// value as written, before expansion
+    pub source: Source,
+    pub origin: Origin,
+    /// variables the template refers to
+    pub references: Vec<String>,
+    pub secret: bool,
+    pub overrides: Vec<Origin>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Toml,
+    Dotenv,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    /// dotenv for `.env`, `.env.*` and `*.env` files, json and yaml by their
+    /// extension, toml otherwise
+    pub fn detect(path: &Path) -> Self {
+        let name = path.file_name().and_then(|name| name.to_str());
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        match (name, extension) {
+            (Some(name), _) if name == ".env" || name.starts_with(".env.") => Self::Dotenv,
+            (_, Some("env")) => Self::Dotenv,
+            (_, Some("json")) => Self::Json,
+            (_, Some("yaml" | "yml")) => Self::Yaml,
+            _ => Self::Toml,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Resolver {
+    files: Vec<PathBuf>,
+    stage: Option<String>,
+    overrides: bool,
+    format: Option<Format>,
+    key: Option<Key>,
+    schema: Option<PathBuf>,
+}
+
+impl Resolver {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            stage: None,
+            overrides: false,
+            format: None,
+            key: None,
+            schema: None,
+        }
+    }
+
+    pub fn stage(mut self, stage: Option<String>) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// let later files win instead of failing on duplicate keys
+    pub fn overrides(mut self, overrides: bool) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// parse every file with this format instead of detecting it
+    pub fn format(mut self, format: Option<Format>) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// key to decrypt encrypted values with
+    pub fn key(mut self, key: Option<Key>) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// schema file whose rules take precedence over the ones in the env files
+    pub fn schema(mut self, schema: Option<PathBuf>) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn get(&self) -> Result<Env> {
+        let variables = self.resolve()?;
+        Ok(variables
+            .into_iter()
+            .map(|variable| (variable.key, variable.value))
+            .collect())
+    }
+
+    /// every variable, failing with all violations of the schema at once
+    pub fn resolve(&self) -> Result<Vec<Variable>> {
+        let (definitions, schema) = self.definitions()?;
+        let mut expander = Expander::new(&definitions, self.key.as_ref());
+        let mut result = Vec::default();
+        for definition in &definitions {
+            // expanded first so template errors are labelled with the origin
+            let value = expander.resolve(&definition.key)?.unwrap_or_default();
+            let mut references = Vec::default();
+            if let Source::Template(template) = &definition.source {
+                for reference in Template::parse(template)?.references() {
+                    if !references.iter().any(|r| r == reference) {
+                        references.push(reference.to_string());
+                    }
+                }
+            }
+            result.push(Variable {
+                key: definition.key.clone(),
+                value,
+                source: definition.source.clone(),
+                origin: definition.origin.clone(),
+                references,
+                secret: definition.secret,
+                overrides: definition
+                    .overrides
+                    .iter()
+                    .map(|overridden| overridden.origin.clone())
+                    .collect(),
+            });
+        }
+        schema.check(&result)?;
+        Ok(result)
+    }
+
+    /// definitions of every variable as written, without resolving them
+    pub fn load(&self) -> Result<Vec<Definition>> {
+        Ok(self.definitions()?.0)
+    }
+
+    /// definitions including the defaults of the schema, along with the
+    /// schema itself
+    fn definitions(&self) -> Result<(Vec<Definition>, Schema)> {
+        let mut layers = self.layers()?;
+        if let Some(stage) = &self.stage
+            && !layers.iter().any(|layer| layer.staged)
+        {
+            bail!("stage not found: {stage}");
+        }
+        let mut result: Vec<Definition> = Vec::default();
+        let mut schema = Schema::default();
+        for layer in &mut layers {
+            schema.extend(std::mem::take(&mut layer.schema));
+        }
+        if let Some(path) = &self.schema {
+            schema.extend(Self::schema_file(path)?);
+        }
+        for definition in layers.into_iter().flat_map(|layer| layer.definitions) {
+            match result.iter_mut().find(|d| d.key == definition.key) {
+                None => result.push(definition),
+                Some(entry) if self.overrides => {
+                    let mut overrides = std::mem::take(&mut entry.overrides);
+                    let overridden = std::mem::replace(entry, definition);
+                    overrides.push(overridden);
+                    entry.overrides = overrides;
+                }
+                Some(entry) => {
+                    let message = format!("duplicate environment variable: {}", definition.key);
+                    let error = Diagnostic::new(message)
+                        .label(&entry.origin, "first defined here")
+                        .label(
+                            &definition.origin,
+                            "defined again here, use --override to let it win",
+                        );
+                    return Err(error.into());
+                }
+            }
+        }
+        result.extend(schema.defaults(&result));
+        Ok((result, schema))
+    }
+
+    /// every file the env is read from, including the ones extended and
+    /// the schema
+    pub fn paths(&self) -> Result<Vec<PathBuf>> {
+        let mut result: Vec<_> = self.layers()?.into_iter().map(|layer| layer.path).collect();
+        if let Some(path) = &self.schema {
+            let path = fs::canonicalize(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            result.push(path);
+        }
+        Ok(result)
+    }
+
+    fn layers(&self) -> Result<Vec<Layer>> {
+        let mut result = Vec::default();
+        for file in &self.files {
+            self.read(file, &mut Vec::default(), &mut result)?;
+        }
+        Ok(result)
+    }
+
+    fn schema_file(path: &Path) -> Result<Schema> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let path = fs::canonicalize(path)?;
+        let format = match Format::detect(&path) {
+            Format::Dotenv => bail!("schema must be toml, json or yaml: {}", path.display()),
+            format => format,
+        };
+        Toml::schema_file(&text, format, &path).map_err(|error| Self::locate(error, &path))
+    }
+
+    /// adds the layers of a file after the layers of the files it extends,
+    /// a file reached more than once is only read the first time
+    fn read(&self, file: &Path, chain: &mut Vec<PathBuf>, layers: &mut Vec<Layer>) -> Result<()> {
+        let path = fs::canonicalize(file)
+            .with_context(|| format!("failed to read {}", Self::chain(chain, file)))?;
+        if let Some(i) = chain.iter().position(|p| *p == path) {
+            bail!("include cycle: {}", Self::chain(&chain[i..], &path));
+        }
+        if layers.iter().any(|layer| layer.path == path) {
+            return Ok(());
+        }
+        let text = fs::read_to_string(&path)?;
+        let parsed = match self.format.unwrap_or_else(|| Format::detect(&path)) {
+            Format::Dotenv => Dotenv::new(&text).map(|dotenv| {
+                let definitions = dotenv.env(&path);
+                (definitions, false, Vec::default(), Schema::default())
+            }),
+            format => Toml::new(&text, format, self.stage.as_deref()).and_then(|toml| {
+                let extends = toml.extends().to_vec();
+                Ok((toml.env(&path), toml.staged(), extends, toml.schema(&path)?))
+            }),
+        };
+        let (definitions, staged, extends, schema) =
+            parsed.map_err(|error| Self::locate(error, &path))?;
+        Self::validate(&definitions)?;
+        chain.push(path.clone());
+        for extend in extends {
+            let dir = path.parent().unwrap();
+            self.read(&dir.join(extend), chain, layers)?;
+        }
+        chain.pop();
+        layers.push(Layer {
+            path,
+            definitions,
+            staged,
+            schema,
+        });
+        Ok(())
+    }
+
+    /// flattening and uppercasing can map different keys of a file to the
+    /// same name, or to names a shell cannot use
+    fn validate(definitions: &[Definition]) -> Result<()> {
+        for (i, definition) in definitions.iter().enumerate() {
+            let key = &definition.key;
+            if !valid(key) {
+                let error = Diagnostic::new(format!("invalid environment variable name: {key}"))
+                    .label(
+                        &definition.origin,
+                        "names may only contain letters, digits and underscores and cannot start with a digit",
+                    );
+                return Err(error.into());
+            }
+            if let Some(other) = definitions[..i].iter().find(|d| d.key == *key) {
+                let error = Diagnostic::new(format!("key collision: {key}"))
+                    .label(&other.origin, format!("becomes {key}"))
+                    .label(&definition.origin, format!("also becomes {key}"));
+                return Err(error.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// points parse errors at the file they came from
+    fn locate(error: anyhow::Error, path: &Path) -> anyhow::Error {
+        let origin = |position| Origin {
+            file: path.to_path_buf(),
+            path: String::default(),
+            position,
+        };
+        match error.downcast::<ParseError>() {
+            Ok(error) => Diagnostic::new(error.message)
+                .label(&origin(error.position), "")
+                .into(),
+            Err(error) => Diagnostic::new(error.to_string())
+                .label(&origin(None), "")
+                .into(),
+        }
+    }
+
+    fn chain(chain: &[PathBuf], last: &Path) -> String {
+        let mut result: Vec<_> = chain
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        result.push(last.display().to_string());
+        result.join(" -> ")
+    }
+}
+
+/// variables defined by a single file
+#[derive(Debug)]
+struct Layer {
+    path: PathBuf,
+    definitions: Vec<Definition>,
+    staged: bool,
+    schema: Schema,
+}
+
+/// expands references between variables regardless of declaration order
+#[derive(Debug)]
+struct Expander<'a> {
+    definitions: HashMap<&'a str, &'a Definition>,
+    resolved: Current,
+    stack: Vec<&'a str>,
+    key: Option<&'a Key>,
+    /// fail on provided and encrypted values instead of resolving them
+    opaque: bool,
+}
+
+impl<'a> Expander<'a> {
+    fn new(definitions: &'a [Definition], key: Option<&'a Key>) -> Self {
+        Self {
+            definitions: definitions
+                .iter()
+                .map(|definition| (definition.key.as_str(), definition))
+                .collect(),
+            resolved: Current::default(),
+            stack: Vec::default(),
+            key,
+            opaque: false,
+        }
+    }
+
+    fn resolve(&mut self, key: &str) -> Result<Option<String>> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(Some(value.clone()));
+        }
+        let Some((&key, &definition)) = self.definitions.get_key_value(key) else {
+            return Ok(std::env::var(key).ok());
+        };
+        if let Some(i) = self.stack.iter().position(|k| *k == key) {
+            let mut chain = self.stack[i..].to_vec();
+            chain.push(key);
+            bail!("reference cycle: {}", chain.join(" -> "));
+        }
+        self.stack.push(key);
+        let value = self.value(key, definition, &definition.overrides)?;
+        self.stack.pop();
+        self.resolved.insert(key.to_string(), value.clone());
+        Ok(Some(value))
+    }
+
+    /// value of a single definition of a key, a template referencing the
+    /// key itself expands the definition it replaced with --override, or
+    /// the host variable if there is none
+    fn value(
+        &mut self,
+        key: &'a str,
+        definition: &'a Definition,
+        earlier: &'a [Definition],
+    ) -> Result<String> {
+        if self.opaque && !matches!(definition.source, Source::Template(_)) {
+            bail!("{key} is only known when resolving");
+        }
+        let value = match &definition.source {
+            Source::Template(template) => Template::parse(template)
+                .and_then(|template| {
+                    template.render(&mut |name| match (name == key, earlier.split_last()) {
+                        (true, Some((previous, rest))) => self.value(key, previous, rest).map(Some),
+                        (true, None) => Ok(std::env::var(key).ok()),
+                        (false, _) => self.resolve(name),
+                    })
+                })
+                .map_err(|error| match error.downcast::<Diagnostic>() {
+                    // errors of references are already labelled where they happen
+                    Ok(error) => error,
+                    Err(error) => Diagnostic::new(error.to_string()).label(&definition.origin, ""),
+                })?,
+            Source::Provider(provider) => provider.get(key).map_err(|error| {
+                let message = format!("failed to resolve {key} with {provider}: {error:#}");
+                Diagnostic::new(message).label(&definition.origin, "")
+            })?,
+            Source::Encrypted(encrypted) => match self.key {
+                Some(secret) => {
+                    secret
+                        .decrypt(&definition.origin.path, encrypted)
+                        .map_err(|error| {
+                            let message = format!("failed to decrypt {key}: {error:#}");
+                            Diagnostic::new(message).label(&definition.origin, "")
+                        })?
+                }
+                None => {
+                    let message = format!("{key} is encrypted but no key was given");
+                    return Err(Diagnostic::new(message)
+                        .label(&definition.origin, "")
+                        .into());
+                }
+            },
+        };
+        Ok(value)
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value, json};
+
+/// source of a value that is looked up when the env is resolved, like a
+/// command printing a secret or a file holding a token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provider {
+    kind: Kind,
+    /// how long a command or plugin may run
+    timeout: Duration,
+    /// remove surrounding whitespace like the trailing newline of a command
+    trim: bool,
+    /// directory of the file the provider was defined in, commands run and
+    /// relative paths are read from there
+    dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    /// shell command printing the value
+    Command(String),
+    File(String),
+    /// `envee-provider-<name>` executable, given the options as json
+    Plugin(String, Map<String, Value>),
+}
+
+impl Provider {
+    pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn command(command: String) -> Self {
+        Self::new(Kind::Command(command))
+    }
+
+    pub fn file(path: String) -> Self {
+        Self::new(Kind::File(path))
+    }
+
+    pub fn plugin(name: String, options: Map<String, Value>) -> Self {
+        Self::new(Kind::Plugin(name, options))
+    }
+
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            timeout: Self::TIMEOUT,
+            trim: true,
+            dir: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    pub fn dir(mut self, dir: &Path) -> Self {
+        self.dir = Some(dir.to_path_buf());
+        self
+    }
+
+    /// value for the variable named key
+    pub fn get(&self, key: &str) -> Result<String> {
+        let value = match &self.kind {
+            Kind::Command(command) => self.output(Self::shell(command), None)?,
+            Kind::File(path) => {
+                let path = self.path(path);
+                fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?
+            }
+            Kind::Plugin(name, options) => {
+                let input = json!({ "key": key, "options": options });
+                let plugin = Command::new(format!("envee-provider-{name}"));
+                let output = self.output(plugin, Some(input.to_string()))?;
+                let output: Value = serde_json::from_str(&output)
+                    .with_context(|| format!("invalid output of provider {name}"))?;
+                match (output.get("value"), output.get("error")) {
+                    (_, Some(Value::String(error))) => bail!("{error}"),
+                    (Some(Value::String(value)), _) => value.clone(),
+                    _ => bail!("provider {name} must print a json object with a value"),
+                }
+            }
+        };
+        Ok(match self.trim {
+            true => value.trim().to_string(),
+            false => value,
+        })
+    }
+
+    #[cfg(unix)]
+    fn shell(command: &str) -> Command {
+        let mut result = Command::new("sh");
+        result.arg("-c").arg(command);
+        result
+    }
+
+    #[cfg(not(unix))]
+    fn shell(command: &str) -> Command {
+        let mut result = Command::new("cmd");
+        result.arg("/C").arg(command);
+        result
+    }
+
+    /// `~` is the home directory, other relative paths start at the
+    /// directory of the file
+    fn path(&self, path: &str) -> PathBuf {
+        if let Some(rest) = path.strip_prefix("~/")
+            && let Some(home) = std::env::var_os("HOME")
+        {
+            return Path::new(&home).join(rest);
+        }
+        match &self.dir {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+
+    /// stdout of a process, failing if it exits unsuccessfully or runs
+    /// longer than the timeout
+    fn output(&self, mut command: Command, input: Option<String>) -> Result<String> {
+        if let Some(dir) = &self.dir {
+            command.current_dir(dir);
+        }
+        let program = command.get_program().to_string_lossy().to_string();
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {program}"))?;
+        let mut stdin = child.stdin.take().unwrap();
+        // written and read on their own threads so a full pipe never blocks
+        let writer = thread::spawn(move || stdin.write_all(input.unwrap_or_default().as_bytes()));
+        let stdout = Self::read(child.stdout.take().unwrap());
+        let stderr = Self::read(child.stderr.take().unwrap());
+        let status = self.wait(&mut child)?;
+        let _ = writer.join();
+        let stdout = stdout.join().unwrap();
+        let stderr = stderr.join().unwrap();
+        if !status {
+            let stderr = String::from_utf8_lossy(&stderr);
+            bail!("{program} failed: {}", stderr.trim());
+        }
+        String::from_utf8(stdout).with_context(|| format!("{program} printed invalid utf-8"))
+    }
+
+    fn read(mut reader: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut result = Vec::default();
+            let _ = reader.read_to_end(&mut result);
+            result
+        })
+    }
+
+    /// whether the child succeeded, killing it once the timeout passes
+    fn wait(&self, child: &mut Child) -> Result<bool> {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status.success());
+            }
+            if start.elapsed() > self.timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!("timed out after {}s", self.timeout.as_secs_f64());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Display for Provider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            Kind::Command(command) => write!(f, "cmd {command:?}"),
+            Kind::File(path) => write!(f, "file {path:?}"),
+            Kind::Plugin(name, _) => write!(f, "provider {name:?}"),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::{Result, bail};
+use regex::Regex;
+use toml::{Table, Value};
+
+use super::diagnostic::{Diagnostic, ParseError};
+use super::{Definition, Origin, Source, Variable};
+use crate::redact::Redactor;
+
+/// expected type and presence of resolved variables, written as a table of
+/// rules by variable name like `PORT = { type = "port", default = 8080 }`
+/// or just `PORT = "port"`
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    key: String,
+    kind: Type,
+    /// must be set and not empty
+    required: bool,
+    /// template used when no file defines the variable
+    default: Option<String>,
+    description: Option<String>,
+    origin: Origin,
+}
+
+impl Schema {
+    const ATTRIBUTES: &[&str] = &[
+        "type",
+        "required",
+        "default",
+        "description",
+        "values",
+        "pattern",
+    ];
+
+    /// `origin` locates the rule of a variable within its file
+    pub fn new(table: &Table, origin: impl Fn(&str) -> Origin) -> Result<Self> {
+        let mut result = Self::default();
+        for (key, value) in table {
+            let origin = origin(key);
+            let rule = Rule::new(key, value, origin.clone())
+                .map_err(|error| ParseError::new(format!("{error:#}"), origin.position))?;
+            result.rules.push(rule);
+        }
+        Ok(result)
+    }
+
+    /// rules of later schemas replace the ones for the same variable
+    pub fn extend(&mut self, other: Self) {
+        for rule in other.rules {
+            match self.rules.iter_mut().find(|r| r.key == rule.key) {
+                Some(existing) => *existing = rule,
+                None => self.rules.push(rule),
+            }
+        }
+    }
+
+    /// definitions of the defaults of variables no file defines
+    pub fn defaults(&self, definitions: &[Definition]) -> Vec<Definition> {
+        let secrets = super::secrets();
+        self.rules
+            .iter()
+            .filter(|rule| !definitions.iter().any(|d| d.key == rule.key))
+            .filter_map(|rule| {
+                Some(Definition {
+                    key: rule.key.clone(),
+                    source: Source::Template(rule.default.clone()?),
+                    origin: rule.origin.clone(),
+                    secret: secrets.iter().any(|pattern| pattern.matches(&rule.key)),
+                    overrides: Vec::default(),
+                })
+            })
+            .collect()
+    }
+
+    /// every variable not matching its rule, empty values count as not set
+    pub fn check(&self, variables: &[Variable]) -> Result<(), Violations> {
+        let mut violations = Vec::default();
+        for rule in &self.rules {
+            let variable = variables.iter().find(|v| v.key == rule.key);
+            let Some(variable) = variable.filter(|v| !v.value.is_empty()) else {
+                if rule.required {
+                    let message = format!("{} is required but not set", rule.key);
+                    let note = rule.description.as_deref().unwrap_or_default();
+                    violations.push(Diagnostic::new(message).label(&rule.origin, note));
+                }
+                continue;
+            };
+            if rule.kind.matches(&variable.value) {
+                continue;
+            }
+            let value = match variable.secret {
+                true => Redactor::MASK.to_string(),
+                false => format!("{:?}", variable.value),
+            };
+            let message = format!("{} must be {}, got {value}", rule.key, rule.kind);
+            let diagnostic = Diagnostic::new(message)
+                .label(&variable.origin, "")
+                .label(&rule.origin, "declared here");
+            violations.push(diagnostic);
+        }
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(Violations(violations)),
+        }
+    }
+}
+
+impl Rule {
+    fn new(key: &str, value: &Value, origin: Origin) -> Result<Self> {
+        // defaults become variables without going through the names of files
+        if !super::valid(key) {
+            bail!("invalid environment variable name: {key}");
+        }
+        let mut result = Self {
+            key: key.to_string(),
+            kind: Type::String,
+            required: false,
+            default: None,
+            description: None,
+            origin,
+        };
+        let table = match value {
+            Value::String(kind) => {
+                result.kind = Type::new(kind, &Table::default())?;
+                return Ok(result);
+            }
+            Value::Table(table) => table,
+            _ => bail!("schema of {key} must be a type or a table"),
+        };
+        if let Some(attribute) = table
+            .keys()
+            .find(|attribute| !Schema::ATTRIBUTES.contains(&attribute.as_str()))
+        {
+            bail!("invalid schema attribute: {key}.{attribute}");
+        }
+        result.kind = match table.get("type") {
+            None => Type::new("string", table)?,
+            Some(Value::String(kind)) => Type::new(kind, table)?,
+            Some(_) => bail!("type must be a string"),
+        };
+        result.required = match table.get("required") {
+            None => false,
+            Some(Value::Boolean(required)) => *required,
+            Some(_) => bail!("required must be a boolean"),
+        };
+        result.default = match table.get("default") {
+            None => None,
+            Some(Value::String(default)) => Some(default.clone()),
+            Some(default @ (Value::Integer(_) | Value::Float(_) | Value::Boolean(_))) => {
+                Some(default.to_string())
+            }
+            Some(_) => bail!("default must be a scalar"),
+        };
+        result.description = match table.get("description") {
+            None => None,
+            Some(Value::String(description)) => Some(description.clone()),
+            Some(_) => bail!("description must be a string"),
+        };
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Type {
+    String,
+    Int,
+    Bool,
+    Url,
+    Port,
+    Enum(Vec<String>),
+    /// the whole value has to match
+    Regex {
+        pattern: String,
+        regex: Regex,
+    },
+}
+
+impl Type {
+    /// `values` of enums and the `pattern` of regexes are read from the
+    /// table of the rule
+    fn new(kind: &str, table: &Table) -> Result<Self> {
+        match (kind, table.get("values"), table.get("pattern")) {
+            (kind, Some(_), _) if kind != "enum" => bail!("values can only be set for enum"),
+            (kind, _, Some(_)) if kind != "regex" => bail!("pattern can only be set for regex"),
+            ("enum", Some(Value::Array(values)), _) => Ok(Self::Enum(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(value) => Ok(value.clone()),
+                        _ => bail!("values must be strings"),
+                    })
+                    .collect::<Result<_>>()?,
+            )),
+            ("enum", _, _) => bail!("enum must have a list of values"),
+            ("regex", _, Some(Value::String(pattern))) => Ok(Self::Regex {
+                pattern: pattern.clone(),
+                regex: Regex::new(&format!("^(?:{pattern})$"))?,
+            }),
+            ("regex", _, _) => bail!("regex must have a pattern"),
+            ("string", _, _) => Ok(Self::String),
+            ("int", _, _) => Ok(Self::Int),
+            ("bool", _, _) => Ok(Self::Bool),
+            ("url", _, _) => Ok(Self::Url),
+            ("port", _, _) => Ok(Self::Port),
+            (kind, _, _) => {
+                bail!("invalid type: {kind}, expected string, int, bool, url, port, enum or regex")
+            }
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Int => value.parse::<i64>().is_ok(),
+            Self::Bool => matches!(
+                value.to_ascii_lowercase().as_str(),
+                "true" | "false" | "1" | "0" | "yes" | "no"
+            ),
+            Self::Url => value.split_once("://").is_some_and(|(scheme, rest)| {
+                let mut chars = scheme.chars();
+                chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                    && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                    && !rest.is_empty()
+                    && !rest.contains(char::is_whitespace)
+            }),
+            Self::Port => value.parse::<u16>().is_ok_and(|port| port > 0),
+            Self::Enum(values) => values.iter().any(|v| v == value),
+            Self::Regex { regex, .. } => regex.is_match(value),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String => write!(f, "a string"),
+            Self::Int => write!(f, "an int"),
+            Self::Bool => write!(f, "a bool"),
+            Self::Url => write!(f, "a url"),
+            Self::Port => write!(f, "a port"),
+            Self::Enum(values) => write!(f, "one of {}", values.join(", ")),
+            Self::Regex { pattern, .. } => write!(f, "a value matching {pattern:?}"),
+        }
+    }
+}
+
+/// every variable not matching the schema, reported together
+#[derive(Debug)]
+pub struct Violations(Vec<Diagnostic>);
+
+impl Display for Violations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0.len() {
+            1 => write!(f, "1 variable does not match the schema")?,
+            n => write!(f, "{n} variables do not match the schema")?,
+        }
+        for diagnostic in &self.0 {
+            write!(f, "\n\n{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Violations {}
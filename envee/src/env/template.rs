@@ -0,0 +1,207 @@
+use anyhow::{Result, bail};
+
+/// returns the value of a variable, `None` if it is not set anywhere
+pub type Lookup<'a> = dyn FnMut(&str) -> Result<Option<String>> + 'a;
+
+/// value with `$NAME` and `${NAME}` style references, supporting the POSIX
+/// `${NAME:-default}`, `${NAME:?message}` and `${NAME:+alternate}` operators,
+/// a literal `$` is written as `$$`
+#[derive(Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Var(String, Option<Modifier>),
+}
+
+#[derive(Debug)]
+struct Modifier {
+    op: Op,
+    /// with a colon an empty value is treated the same as an unset one
+    colon: bool,
+    word: Template,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Default,
+    Required,
+    Alternate,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser { text, pos: 0 };
+        let template = parser.template(false)?;
+        debug_assert!(parser.peek().is_none());
+        Ok(template)
+    }
+
+    /// names of every variable referenced, including inside operator words
+    pub fn references(&self) -> Vec<&str> {
+        let mut result = Vec::default();
+        for part in &self.parts {
+            if let Part::Var(name, modifier) = part {
+                result.push(name.as_str());
+                if let Some(modifier) = modifier {
+                    result.extend(modifier.word.references());
+                }
+            }
+        }
+        result
+    }
+
+    /// names referenced without an operator, these silently expand to
+    /// nothing when the variable is not set
+    pub fn unguarded(&self) -> Vec<&str> {
+        let mut result = Vec::default();
+        for part in &self.parts {
+            match part {
+                Part::Var(name, None) => result.push(name.as_str()),
+                Part::Var(_, Some(modifier)) => result.extend(modifier.word.unguarded()),
+                Part::Text(_) => (),
+            }
+        }
+        result
+    }
+
+    pub fn render(&self, lookup: &mut Lookup) -> Result<String> {
+        let mut result = String::default();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => result.push_str(text),
+                Part::Var(name, None) => result.push_str(&lookup(name)?.unwrap_or_default()),
+                Part::Var(name, Some(modifier)) => {
+                    result.push_str(&modifier.apply(name, lookup)?);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Modifier {
+    fn apply(&self, name: &str, lookup: &mut Lookup) -> Result<String> {
+        let value = lookup(name)?.filter(|value| !self.colon || !value.is_empty());
+        // operator words are only rendered when used, so their references
+        // are never looked up otherwise
+        match (self.op, value) {
+            (Op::Default, Some(value)) => Ok(value),
+            (Op::Default, None) => self.word.render(lookup),
+            (Op::Required, Some(value)) => Ok(value),
+            (Op::Required, None) => {
+                let message = self.word.render(lookup)?;
+                match message.is_empty() {
+                    true => bail!("{name}: parameter null or not set"),
+                    false => bail!("{name}: {message}"),
+                }
+            }
+            (Op::Alternate, Some(_)) => self.word.render(lookup),
+            (Op::Alternate, None) => Ok(String::default()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn template(&mut self, nested: bool) -> Result<Template> {
+        let mut parts = Vec::default();
+        let mut text = String::default();
+        while let Some(c) = self.peek() {
+            if nested && c == '}' {
+                break;
+            }
+            self.bump();
+            match c {
+                '$' if self.peek() == Some('$') => {
+                    self.bump();
+                    text.push(c);
+                }
+                '$' => match self.variable()? {
+                    Some(part) => {
+                        if !text.is_empty() {
+                            parts.push(Part::Text(std::mem::take(&mut text)));
+                        }
+                        parts.push(part);
+                    }
+                    None => text.push(c),
+                },
+                _ => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// called after a `$`, anything other than a reference is left as text
+    fn variable(&mut self) -> Result<Option<Part>> {
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                let name = self.name();
+                if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                    bail!("invalid variable reference: {}", self.text);
+                }
+                let colon = self.peek() == Some(':');
+                if colon {
+                    self.bump();
+                }
+                let op = match self.peek() {
+                    Some('-') => Some(Op::Default),
+                    Some('?') => Some(Op::Required),
+                    Some('+') => Some(Op::Alternate),
+                    _ if colon => bail!("invalid variable operator: {}", self.text),
+                    _ => None,
+                };
+                let modifier = match op {
+                    None => None,
+                    Some(op) => {
+                        self.bump();
+                        let word = self.template(true)?;
+                        Some(Modifier { op, colon, word })
+                    }
+                };
+                if self.peek() != Some('}') {
+                    bail!("unclosed variable reference: {}", self.text);
+                }
+                self.bump();
+                Ok(Some(Part::Var(name, modifier)))
+            }
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                Ok(Some(Part::Var(self.name(), None)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c != '_' && !c.is_ascii_alphanumeric() {
+                break;
+            }
+            self.bump();
+        }
+        self.text[start..self.pos].to_string()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+}
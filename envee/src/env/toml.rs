@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use toml::de::{DeTable, DeValue};
+use toml::{Table, Value};
+
+use super::diagnostic::ParseError;
+use super::schema::Schema;
+use super::{Definition, Format, Origin, Provider, Source};
+use crate::pattern::Pattern;
+
+type Data = Vec<Entry>;
+/// line and column of each key, by path
+type Positions = HashMap<Vec<String>, (usize, usize)>;
+
+/// flattened value along with the path it was found at
+#[derive(Debug)]
+struct Entry {
+    path: Vec<String>,
+    name: String,
+    source: Source,
+    /// set explicitly, otherwise the name decides
+    secret: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct Toml {
+    data: Data,
+    stage: Option<Data>,
+    extends: Vec<String>,
+    schema: Option<Table>,
+    positions: Positions,
+    /// prepended to every name
+    prefix: String,
+    /// names of variables that are secret regardless of their definition
+    secrets: Vec<Pattern>,
+}
+
+impl Toml {
+    /// top level table holding one sub-table per stage
+    const STAGES: &str = "stages";
+    /// top level path or list of paths to files this one is layered over
+    const EXTENDS: &str = "extends";
+    /// top level table holding settings for the whole file, or a table in
+    /// any other table holding settings for that table
+    const SETTINGS: &str = "envee";
+    /// top level table holding the rules of variables, by name
+    const SCHEMA: &str = "schema";
+
+    pub fn new(text: &str, format: Format, stage: Option<&str>) -> Result<Self> {
+        let (mut table, positions) = Self::parse(text, format)?;
+        let settings = Settings::new(table.remove(Self::SETTINGS))?;
+        let extends = match table.remove(Self::EXTENDS) {
+            None => Vec::default(),
+            Some(Value::String(path)) => vec![path],
+            Some(Value::Array(paths)) => paths
+                .into_iter()
+                .map(|path| match path {
+                    Value::String(path) => Ok(path),
+                    _ => bail!("{} must only contain paths", Self::EXTENDS),
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => bail!("{} must be a path or a list of paths", Self::EXTENDS),
+        };
+        let stages = match table.remove(Self::STAGES) {
+            None => Table::default(),
+            Some(Value::Table(stages)) => stages,
+            Some(_) => bail!("{} must be a table", Self::STAGES),
+        };
+        let schema = match table.remove(Self::SCHEMA) {
+            None => None,
+            Some(Value::Table(schema)) => Some(schema),
+            Some(_) => bail!("{} must be a table", Self::SCHEMA),
+        };
+        let mut result = Self {
+            data: Data::default(),
+            stage: None,
+            extends,
+            schema,
+            positions,
+            prefix: settings.prefix,
+            secrets: settings.secrets,
+        };
+        let rules = &settings.rules;
+        result.stage = match stage.map(|name| (name, stages.get(name))) {
+            None | Some((_, None)) => None,
+            Some((name, Some(Value::Table(stage)))) => {
+                // stage paths are kept whole so they can be located in the file
+                let path = vec![Self::STAGES.to_string(), name.to_string()];
+                Some(result.table(stage, path, "", "", rules)?)
+            }
+            Some((name, Some(_))) => bail!("stage must be a table: {name}"),
+        };
+        result.data = result.table(&table, Vec::default(), "", "", rules)?;
+        Ok(result)
+    }
+
+    /// json and yaml documents are read into the same table so nested keys
+    /// and scalars are flattened identically across formats
+    fn parse(text: &str, format: Format) -> Result<(Table, Positions)> {
+        let table: Table = match format {
+            Format::Json => serde_json::from_str(text).map_err(|error| {
+                let message = error.to_string();
+                // the position is shown by the diagnostic instead
+                let message = message.rsplit_once(" at line ").map_or(&*message, |m| m.0);
+                let position = (error.line(), error.column().max(1));
+                ParseError::new(message, Some(position))
+            })?,
+            Format::Yaml => serde_norway::from_str(text).map_err(|error| {
+                let position = error.location().map(|l| (l.line(), l.column()));
+                ParseError::new(error.to_string(), position)
+            })?,
+            _ => text.parse().map_err(|error: toml::de::Error| {
+                let position = error.span().map(|span| Origin::position(text, span.start));
+                ParseError::new(error.message().trim(), position)
+            })?,
+        };
+        // only toml keeps track of where keys are, json and yaml are parsed
+        // without spans
+        let mut positions = Positions::default();
+        if format == Format::Toml {
+            let document = DeTable::parse(text)?;
+            Self::positions(text, document.get_ref(), &[], &mut positions);
+        }
+        Ok((table, positions))
+    }
+
+    fn positions(text: &str, table: &DeTable, path: &[String], result: &mut Positions) {
+        for (key, value) in table {
+            let mut path = path.to_vec();
+            path.push(key.get_ref().to_string());
+            result.insert(path.clone(), Origin::position(text, key.span().start));
+            match value.get_ref() {
+                DeValue::Table(table) => Self::positions(text, table, &path, result),
+                DeValue::Array(values) => {
+                    for (i, value) in values.iter().enumerate() {
+                        let mut path = path.clone();
+                        path.push(i.to_string());
+                        result.insert(path.clone(), Origin::position(text, value.span().start));
+                        if let DeValue::Table(table) = value.get_ref() {
+                            Self::positions(text, table, &path, result);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn staged(&self) -> bool {
+        self.stage.is_some()
+    }
+
+    /// paths relative to the directory of this file
+    pub fn extends(&self) -> &[String] {
+        &self.extends
+    }
+
+    /// `base` is the name of the table holding the value
+    fn flatten(&self, value: &Value, path: Vec<String>, base: &str, rules: &Rules) -> Result<Data> {
+        let name = rules.name(base, path.last().unwrap());
+        match value {
+            Value::Array(values) => self.array(values, path, &name, rules),
+            Value::Table(v) => match Spec::new(v)? {
+                Some((spec, secret)) => {
+                    let mut rules = rules.clone();
+                    rules.secret = secret.or(rules.secret);
+                    match spec {
+                        Spec::Value(value, join) => {
+                            if let Some(join) = join {
+                                rules.join = join.to_string();
+                            }
+                            self.flatten(value, path, base, &rules)
+                        }
+                        Spec::Provider(provider) => Ok(vec![Entry {
+                            path,
+                            name,
+                            source: Source::Provider(provider),
+                            secret: rules.secret,
+                        }]),
+                        Spec::Encrypted(encrypted) => Ok(vec![Entry {
+                            path,
+                            name,
+                            source: Source::Encrypted(encrypted),
+                            secret: Some(true),
+                        }]),
+                    }
+                }
+                None => self.table(v, path, base, &name, rules),
+            },
+            _ => Ok(vec![Entry {
+                path,
+                name,
+                source: Source::Template(Self::scalar(value).unwrap()),
+                secret: rules.secret,
+            }]),
+        }
+    }
+
+    /// keys of a table named after the table, unless it opts out of nesting
+    fn table(
+        &self,
+        table: &Table,
+        path: Vec<String>,
+        base: &str,
+        name: &str,
+        rules: &Rules,
+    ) -> Result<Data> {
+        let (rules, nest) = rules.table(table.get(Self::SETTINGS))?;
+        let base = if nest { name } else { base };
+        let mut result = Data::default();
+        for (key, value) in table {
+            if key == Self::SETTINGS {
+                continue;
+            }
+            let mut path = path.clone();
+            path.push(key.to_string());
+            result.extend(self.flatten(value, path, base, &rules)?);
+        }
+        Ok(result)
+    }
+
+    /// arrays of tables are expanded by index, arrays of scalars are joined
+    fn array(
+        &self,
+        values: &[Value],
+        path: Vec<String>,
+        name: &str,
+        rules: &Rules,
+    ) -> Result<Data> {
+        if !values.is_empty() && values.iter().all(Value::is_table) {
+            let mut result = Data::default();
+            for (i, value) in values.iter().enumerate() {
+                let mut path = path.clone();
+                path.push(i.to_string());
+                result.extend(self.flatten(value, path, name, rules)?);
+            }
+            return Ok(result);
+        }
+        match values.iter().map(Self::scalar).collect::<Option<Vec<_>>>() {
+            Some(values) => Ok(vec![Entry {
+                path,
+                name: name.to_string(),
+                source: Source::Template(values.join(&rules.join)),
+                secret: rules.secret,
+            }]),
+            None => {
+                let message = format!(
+                    "toml arrays must contain only scalars or only tables: {}",
+                    path.join(".")
+                );
+                Err(ParseError::new(message, self.positions.get(&path).copied()).into())
+            }
+        }
+    }
+
+    fn scalar(value: &Value) -> Option<String> {
+        match value {
+            Value::String(v) => Some(v.to_string()),
+            Value::Integer(v) => Some(v.to_string()),
+            Value::Float(v) => Some(v.to_string()),
+            Value::Boolean(v) => Some(v.to_string()),
+            Value::Datetime(v) => Some(v.to_string()),
+            Value::Array(_) | Value::Table(_) => None,
+        }
+    }
+
+    pub fn env(&self, file: &Path) -> Vec<Definition> {
+        let mut result = self.definitions(file, &self.data);
+        // stage keys are layered over the shared top level keys, stage keys
+        // colliding with each other are kept so they are reported
+        if let Some(data) = &self.stage {
+            let mut staged = Vec::default();
+            for definition in self.definitions(file, data) {
+                match result.iter().position(|d| d.key == definition.key) {
+                    Some(i) if !staged.contains(&i) => {
+                        result[i] = definition;
+                        staged.push(i);
+                    }
+                    _ => {
+                        staged.push(result.len());
+                        result.push(definition);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    fn definitions(&self, file: &Path, data: &Data) -> Vec<Definition> {
+        let mut result = Vec::default();
+        for entry in data {
+            let source = match &entry.source {
+                Source::Provider(provider) => {
+                    Source::Provider(provider.clone().dir(file.parent().unwrap()))
+                }
+                source => source.clone(),
+            };
+            let key = format!("{}{}", self.prefix, entry.name);
+            let secret = entry
+                .secret
+                .unwrap_or_else(|| self.secrets.iter().any(|pattern| pattern.matches(&key)));
+            result.push(Definition {
+                key,
+                source,
+                origin: Self::origin(&self.positions, file, &entry.path),
+                secret,
+                overrides: Vec::default(),
+            });
+        }
+        result
+    }
+
+    /// rules of the `schema` table, keyed by the full name of the variable
+    pub fn schema(&self, file: &Path) -> Result<Schema> {
+        let Some(table) = &self.schema else {
+            return Ok(Schema::default());
+        };
+        Schema::new(table, |key| {
+            let path = [Self::SCHEMA.to_string(), key.to_string()];
+            Self::origin(&self.positions, file, &path)
+        })
+    }
+
+    /// schema file holding nothing but rules at its top level
+    pub fn schema_file(text: &str, format: Format, file: &Path) -> Result<Schema> {
+        let (table, positions) = Self::parse(text, format)?;
+        Schema::new(&table, |key| {
+            Self::origin(&positions, file, &[key.to_string()])
+        })
+    }
+
+    fn origin(positions: &Positions, file: &Path, path: &[String]) -> Origin {
+        Origin {
+            file: file.to_path_buf(),
+            path: path.join("."),
+            position: positions.get(path).copied(),
+        }
+    }
+}
+
+/// settings for the whole file, read from the top level `envee` table
+#[derive(Debug)]
+struct Settings {
+    rules: Rules,
+    prefix: String,
+    /// replaces the default patterns of secret names
+    secrets: Vec<Pattern>,
+}
+
+impl Settings {
+    fn new(value: Option<Value>) -> Result<Self> {
+        let mut result = Self {
+            rules: Rules::default(),
+            prefix: String::default(),
+            secrets: super::secrets(),
+        };
+        let table = match value {
+            None => Table::default(),
+            Some(Value::Table(table)) => table,
+            Some(_) => bail!("{} must be a table", Toml::SETTINGS),
+        };
+        for (key, value) in &table {
+            if result.rules.set(key, value)? {
+                continue;
+            }
+            match (key.as_str(), value) {
+                ("prefix", Value::String(prefix)) => result.prefix = prefix.clone(),
+                ("secrets", Value::Array(patterns)) => {
+                    result.secrets = patterns
+                        .iter()
+                        .map(|pattern| match pattern {
+                            Value::String(pattern) => Ok(pattern.parse()?),
+                            _ => bail!("{}.secrets must only contain patterns", Toml::SETTINGS),
+                        })
+                        .collect::<Result<_>>()?;
+                }
+                (key, _) => bail!("invalid setting: {}.{key}", Toml::SETTINGS),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// how names are built and arrays joined, set for the whole file and
+/// changed for a table and everything below it by its own `envee` table
+#[derive(Debug, Clone)]
+struct Rules {
+    /// separator used to join arrays of scalars
+    join: String,
+    /// separator between the keys of nested tables
+    separator: String,
+    case: Case,
+    /// values are masked when shown, unset leaves it to the name
+    secret: Option<bool>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            join: ",".into(),
+            separator: "_".into(),
+            case: Case::Upper,
+            secret: None,
+        }
+    }
+}
+
+impl Rules {
+    /// name of a key inside the table named `base`
+    fn name(&self, base: &str, key: &str) -> String {
+        let key = match self.case {
+            Case::Upper => key.to_uppercase(),
+            Case::Lower => key.to_lowercase(),
+            Case::Preserve => key.to_string(),
+        };
+        match base.is_empty() {
+            true => key,
+            false => format!("{base}{}{key}", self.separator),
+        }
+    }
+
+    /// rules for the keys of a table along with whether they are nested
+    /// under the name of the table
+    fn table(&self, settings: Option<&Value>) -> Result<(Self, bool)> {
+        let mut result = self.clone();
+        let mut nest = true;
+        let table = match settings {
+            None => return Ok((result, nest)),
+            Some(Value::Table(table)) => table,
+            Some(_) => bail!("{} must be a table", Toml::SETTINGS),
+        };
+        for (key, value) in table {
+            if result.set(key, value)? {
+                continue;
+            }
+            match (key.as_str(), value) {
+                ("nest", Value::Boolean(value)) => nest = *value,
+                (key, _) => bail!("invalid setting: {}.{key}", Toml::SETTINGS),
+            }
+        }
+        Ok((result, nest))
+    }
+
+    /// applies a setting shared by files and tables, false if it is not one
+    fn set(&mut self, key: &str, value: &Value) -> Result<bool> {
+        match (key, value) {
+            ("join", Value::String(join)) => self.join = join.clone(),
+            ("separator", Value::String(separator)) => self.separator = separator.clone(),
+            ("case", Value::String(case)) => self.case = case.parse()?,
+            ("secret", Value::Boolean(secret)) => self.secret = Some(*secret),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// case of the keys in names
+#[derive(Debug, Clone, Copy)]
+enum Case {
+    Upper,
+    Lower,
+    Preserve,
+}
+
+impl FromStr for Case {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "upper" => Ok(Self::Upper),
+            "lower" => Ok(Self::Lower),
+            "preserve" => Ok(Self::Preserve),
+            _ => bail!("invalid case: {s}, expected upper, lower or preserve"),
+        }
+    }
+}
+
+/// path of an encrypted value in the value itself as written by
+/// `envee encrypt`, like `{ envee.encrypted = "..." }`
+pub const ENCRYPTED: [&str; 2] = [Toml::SETTINGS, "encrypted"];
+
+/// value with attributes like `{ envee.value = [], envee.join = ":" }` or a
+/// value looked up when resolving like `{ envee.cmd = "pass show db" }`,
+/// or an encrypted value like `{ envee.encrypted = "..." }`, marked by
+/// setting its source in the `envee` table of the value, any of them can be
+/// marked `secret = true`
+#[derive(Debug)]
+enum Spec<'a> {
+    Value(&'a Value, Option<&'a str>),
+    Provider(Provider),
+    Encrypted(String),
+}
+
+impl<'a> Spec<'a> {
+    /// keys holding the value, set in the `envee` table of the value so
+    /// they cannot be mistaken for regular keys
+    const MARKED: &'static [&'static str] = &["value", "cmd", "file", "provider", "encrypted"];
+
+    /// the value of a table that is a spec along with whether it is marked
+    /// secret, none for regular tables of nested keys
+    fn new(table: &'a Table) -> Result<Option<(Self, Option<bool>)>> {
+        let Some(table) = Self::find(table)? else {
+            return Ok(None);
+        };
+        let sources: Vec<_> = Self::MARKED
+            .iter()
+            .copied()
+            .filter(|source| table.contains_key(*source))
+            .collect();
+        let source = match sources[..] {
+            [source] => source,
+            _ => bail!("only one of {} can be set", sources.join(", ")),
+        };
+        let allowed: &[&str] = match source {
+            "value" => &["join"],
+            "provider" => &["trim", "timeout", "options"],
+            "encrypted" => &[],
+            _ => &["trim", "timeout"],
+        };
+        if let Some(key) = table
+            .keys()
+            .find(|key| *key != source && *key != "secret" && !allowed.contains(&key.as_str()))
+        {
+            bail!("{key} cannot be set for {source}");
+        }
+        let secret = match table.get("secret") {
+            None => None,
+            Some(Value::Boolean(secret)) => Some(*secret),
+            Some(_) => bail!("secret must be a boolean"),
+        };
+        let provider = match (source, &table[source]) {
+            ("encrypted", Value::String(encrypted)) => {
+                return Ok(Some((Self::Encrypted(encrypted.clone()), secret)));
+            }
+            ("value", value) => {
+                let join = match table.get("join") {
+                    None => None,
+                    Some(Value::String(join)) => Some(join.as_str()),
+                    Some(_) => bail!("join must be a string"),
+                };
+                return Ok(Some((Self::Value(value, join), secret)));
+            }
+            ("cmd", Value::String(command)) => Provider::command(command.clone()),
+            ("file", Value::String(path)) => Provider::file(path.clone()),
+            ("provider", Value::String(name)) => {
+                let options = match table.get("options") {
+                    None => serde_json::Map::default(),
+                    Some(options @ Value::Table(_)) => match serde_json::to_value(options)? {
+                        serde_json::Value::Object(options) => options,
+                        _ => unreachable!(),
+                    },
+                    Some(_) => bail!("options must be a table"),
+                };
+                Provider::plugin(name.clone(), options)
+            }
+            (source, _) => bail!("{source} must be a string"),
+        };
+        let provider = match table.get("trim") {
+            None => provider,
+            Some(Value::Boolean(trim)) => provider.trim(*trim),
+            Some(_) => bail!("trim must be a boolean"),
+        };
+        let provider = match table.get("timeout") {
+            None => provider,
+            Some(Value::Integer(seconds)) if *seconds > 0 => {
+                provider.timeout(Duration::from_secs(*seconds as u64))
+            }
+            Some(Value::Float(seconds)) if *seconds > 0.0 => {
+                match Duration::try_from_secs_f64(*seconds) {
+                    Ok(timeout) => provider.timeout(timeout),
+                    Err(_) => bail!("timeout must be a finite number of seconds"),
+                }
+            }
+            Some(_) => bail!("timeout must be a positive number of seconds"),
+        };
+        Ok(Some((Self::Provider(provider), secret)))
+    }
+
+    /// table holding the source and attributes of a spec, the `envee` table
+    /// of the value when a source is set in it
+    fn find(table: &'a Table) -> Result<Option<&'a Table>> {
+        if let Some(Value::Table(settings)) = table.get(Toml::SETTINGS)
+            && Self::MARKED
+                .iter()
+                .any(|source| settings.contains_key(*source))
+        {
+            if let Some(key) = table.keys().find(|key| *key != Toml::SETTINGS) {
+                bail!("{key} cannot be set next to a value");
+            }
+            return Ok(Some(settings));
+        }
+        Ok(None)
+    }
+}
@@ -1,2 +1,9 @@
 pub mod cli;
+pub mod crypto;
 pub mod env;
+pub mod output;
+pub mod pattern;
+pub mod project;
+pub mod redact;
+pub mod shell;
+pub mod trust;
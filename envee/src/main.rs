@@ -1,8 +1,10 @@
+use std::process::ExitCode;
+
 use anyhow::Result;
 use clap::Parser;
 
 use envee::{cli::Cli, cli::Task};
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     Cli::parse().run()
 }
@@ -0,0 +1,136 @@
+use anyhow::{Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+
+use crate::shell::Shell;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// posix shell `export KEY='value'` lines
+    Export,
+    /// bash and zsh `export KEY=$'value'` lines
+    Bash,
+    /// `KEY="value"` lines
+    Dotenv,
+    /// single object of keys to values
+    Json,
+    /// fish shell `set -gx KEY 'value'` lines
+    Fish,
+    /// powershell `$env:KEY = 'value'` lines
+    Powershell,
+    /// docker `--env-file` with unquoted values
+    Docker,
+    /// systemd `EnvironmentFile`
+    Systemd,
+    /// kubernetes configmap manifest
+    Configmap,
+    /// kubernetes secret manifest
+    Secret,
+}
+
+impl Format {
+    /// whether the output is also read by people, the others are mostly
+    /// loaded by programs that would take masked values for the real ones
+    pub fn readable(&self) -> bool {
+        !matches!(
+            self,
+            Self::Dotenv
+                | Self::Json
+                | Self::Docker
+                | Self::Systemd
+                | Self::Configmap
+                | Self::Secret
+        )
+    }
+
+    /// `name` is used for the metadata of kubernetes manifests
+    pub fn render(&self, env: &[(String, String)], name: &str) -> Result<String> {
+        let result = match self {
+            Self::Export => Self::lines(env, |key, value| {
+                format!("export {key}={}", Shell::Posix.quote(value))
+            }),
+            Self::Bash => Self::lines(env, |key, value| {
+                format!("export {key}={}", Shell::Bash.quote(value))
+            }),
+            Self::Dotenv => Self::lines(env, |key, value| {
+                format!("{key}=\"{}\"", Self::escape(value, true))
+            }),
+            Self::Json => {
+                let data = Self::map(env, |value| value.to_string());
+                format!("{}\n", serde_json::to_string_pretty(&data)?)
+            }
+            Self::Fish => Self::lines(env, |key, value| {
+                format!("set -gx {key} {}", Shell::Fish.quote(value))
+            }),
+            Self::Powershell => Self::lines(env, |key, value| {
+                format!("$env:{key} = {}", Shell::Powershell.quote(value))
+            }),
+            Self::Docker => {
+                if let Some((key, _)) = env.iter().find(|(_, value)| value.contains('\n')) {
+                    bail!("docker env files do not support newlines: {key}");
+                }
+                Self::lines(env, |key, value| format!("{key}={value}"))
+            }
+            Self::Systemd => Self::lines(env, |key, value| {
+                format!("{key}=\"{}\"", Self::escape(value, false))
+            }),
+            Self::Configmap => {
+                Self::manifest("ConfigMap", name, Self::map(env, |value| value.to_string()))?
+            }
+            Self::Secret => Self::manifest(
+                "Secret",
+                name,
+                Self::map(env, |value| STANDARD.encode(value)),
+            )?,
+        };
+        Ok(result)
+    }
+
+    fn lines<F>(env: &[(String, String)], f: F) -> String
+    where
+        F: Fn(&str, &str) -> String,
+    {
+        env.iter()
+            .map(|(key, value)| format!("{}\n", f(key, value)))
+            .collect()
+    }
+
+    fn map<F>(env: &[(String, String)], f: F) -> Value
+    where
+        F: Fn(&str) -> String,
+    {
+        let map: Map<_, _> = env
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::String(f(value))))
+            .collect();
+        Value::Object(map)
+    }
+
+    fn manifest(kind: &str, name: &str, data: Value) -> Result<String> {
+        let manifest = json!({
+            "apiVersion": "v1",
+            "kind": kind,
+            "metadata": { "name": name },
+            "data": data,
+        });
+        Ok(serde_norway::to_string(&manifest)?)
+    }
+
+    /// body of a double quoted value, systemd keeps newlines as is since it
+    /// does not interpret `\n`
+    fn escape(value: &str, newline: bool) -> String {
+        let mut result = String::default();
+        for c in value.chars() {
+            match c {
+                '\\' | '"' | '$' | '`' => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                '\n' if newline => result.push_str("\\n"),
+                _ => result.push(c),
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,39 @@
+use std::convert::Infallible;
+use std::str::FromStr;
+
+/// glob style pattern for variable names, `*` matches any number of
+/// characters and `?` matches exactly one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    chars: Vec<char>,
+}
+
+impl FromStr for Pattern {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            chars: s.chars().collect(),
+        })
+    }
+}
+
+impl Pattern {
+    pub fn matches(&self, name: &str) -> bool {
+        let name: Vec<_> = name.chars().collect();
+        Self::matches_from(&self.chars, &name)
+    }
+
+    fn matches_from(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => {
+                Self::matches_from(&pattern[1..], name)
+                    || (!name.is_empty() && Self::matches_from(pattern, &name[1..]))
+            }
+            (Some('?'), Some(_)) => Self::matches_from(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => Self::matches_from(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+}
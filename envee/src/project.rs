@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use toml::{Table, Value};
+
+use crate::env;
+
+/// defaults for every command run in a directory of the project, read from
+/// the `.envee.toml` found in the current directory or its parents
+#[derive(Debug, Default, PartialEq)]
+pub struct Project {
+    /// env files used unless files are passed
+    pub files: Vec<PathBuf>,
+    /// stage used unless one or other files are passed
+    pub stage: Option<String>,
+    pub overrides: bool,
+    pub schema: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+}
+
+impl Project {
+    pub const FILE: &str = ".envee.toml";
+
+    /// project of the current directory, none outside of a project
+    pub fn current() -> Result<Option<Self>> {
+        match env::discover(&std::env::current_dir()?, Self::FILE) {
+            Some(path) => Self::read(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// paths in the file are relative to the directory it is in
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse(&text, path.parent().unwrap())
+            .with_context(|| format!("invalid project config {}", path.display()))
+    }
+
+    fn parse(text: &str, dir: &Path) -> Result<Self> {
+        let table: Table = text.parse()?;
+        let path = |path: &str| match path.strip_prefix("~/") {
+            Some(rest) => match std::env::var_os("HOME") {
+                Some(home) => Path::new(&home).join(rest),
+                None => dir.join(path),
+            },
+            None => dir.join(path),
+        };
+        let mut result = Self::default();
+        for (key, value) in &table {
+            match (key.as_str(), value) {
+                ("files", Value::String(file)) => result.files = vec![path(file)],
+                ("files", Value::Array(files)) => {
+                    result.files = files
+                        .iter()
+                        .map(|file| match file {
+                            Value::String(file) => Ok(path(file)),
+                            _ => bail!("files must only contain paths"),
+                        })
+                        .collect::<Result<_>>()?;
+                }
+                ("stage", Value::String(stage)) => result.stage = Some(stage.clone()),
+                ("override", Value::Boolean(overrides)) => result.overrides = *overrides,
+                ("schema", Value::String(schema)) => result.schema = Some(path(schema)),
+                ("key_file", Value::String(file)) => result.key_file = Some(path(file)),
+                (key, _) => bail!("invalid setting: {key}"),
+            }
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::{self, Read, Write};
+
+/// replaces secret values in text, longer secrets win over the ones they
+/// start with
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    secrets: Vec<Vec<u8>>,
+}
+
+impl Redactor {
+    /// shown instead of a secret value
+    pub const MASK: &str = "****";
+
+    /// empty values are ignored, they would match everywhere
+    pub fn new<I, S>(secrets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut secrets: Vec<Vec<u8>> = secrets
+            .into_iter()
+            .map(|secret| secret.as_ref().as_bytes().to_vec())
+            .filter(|secret| !secret.is_empty())
+            .collect();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    pub fn redact(&self, data: &[u8]) -> Vec<u8> {
+        self.scan(data, true).0
+    }
+
+    /// text with secrets replaced, which only ever match whole characters
+    pub fn redact_str(&self, text: &str) -> String {
+        String::from_utf8_lossy(&self.redact(text.as_bytes())).into_owned()
+    }
+
+    /// copies everything read to the writer with secrets replaced, holding
+    /// back only the bytes that may be the start of a secret split across
+    /// reads
+    pub fn copy(&self, mut reader: impl Read, mut writer: impl Write) -> io::Result<()> {
+        let mut pending = Vec::default();
+        let mut buffer = [0; 8192];
+        loop {
+            let n = match reader.read(&mut buffer) {
+                Ok(n) => n,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            pending.extend_from_slice(&buffer[..n]);
+            let (output, consumed) = self.scan(&pending, n == 0);
+            pending.drain(..consumed);
+            writer.write_all(&output)?;
+            writer.flush()?;
+            if n == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// redacted data along with how much of it was consumed, unless at the
+    /// end the scan stops where a secret may continue in data not read yet
+    fn scan(&self, data: &[u8], end: bool) -> (Vec<u8>, usize) {
+        let mut result = Vec::with_capacity(data.len());
+        let mut i = 0;
+        'outer: while i < data.len() {
+            let rest = &data[i..];
+            for secret in &self.secrets {
+                if rest.starts_with(secret) {
+                    result.extend_from_slice(Self::MASK.as_bytes());
+                    i += secret.len();
+                    continue 'outer;
+                }
+                if !end && secret.starts_with(rest) {
+                    return (result, i);
+                }
+            }
+            result.push(data[i]);
+            i += 1;
+        }
+        (result, i)
+    }
+}
@@ -0,0 +1,66 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    /// any posix compatible shell
+    Posix,
+    /// bash and zsh
+    Bash,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    /// quotes a value so the shell reads it back verbatim, without expanding
+    /// variables, commands or escape sequences inside of it
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            Self::Posix => Self::single(value),
+            Self::Bash => match value.chars().any(|c| c.is_control()) {
+                true => Self::ansi(value),
+                false => Self::single(value),
+            },
+            Self::Fish => {
+                let value = value.replace('\\', "\\\\").replace('\'', "\\'");
+                format!("'{value}'")
+            }
+            Self::Powershell => {
+                // powershell also treats typographic single quotes as quotes
+                let mut result = String::from("'");
+                for c in value.chars() {
+                    if matches!(c, '\'' | '‘' | '’' | '‚' | '‛') {
+                        result.push(c);
+                    }
+                    result.push(c);
+                }
+                result.push('\'');
+                result
+            }
+        }
+    }
+
+    /// a single quote cannot be escaped inside single quotes, so the string
+    /// is closed, followed by an escaped quote and reopened
+    fn single(value: &str) -> String {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+
+    /// `$'...'` keeps control characters like newlines on a single line
+    fn ansi(value: &str) -> String {
+        let mut result = String::from("$'");
+        for c in value.chars() {
+            match c {
+                '\\' | '\'' => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                c if c.is_ascii_control() => result.push_str(&format!("\\x{:02x}", c as u32)),
+                c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+                c => result.push(c),
+            }
+        }
+        result.push('\'');
+        result
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+/// files allowed to be loaded without asking, along with the hash of the
+/// contents they were allowed with
+#[derive(Debug)]
+pub struct Trust {
+    path: PathBuf,
+    entries: Vec<(String, PathBuf)>,
+}
+
+impl Trust {
+    /// kept in `envee/allow` of the data directory, one `hash path` per line
+    pub fn load() -> Result<Self> {
+        let dir = match std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::var_os("HOME")
+                .map(|home| Path::new(&home).join(".local/share"))
+                .context("no data directory, set XDG_DATA_HOME or HOME")?,
+        };
+        let path = dir.join("envee").join("allow");
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::default(),
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        let entries = text
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(hash, file)| (hash.to_string(), PathBuf::from(file)))
+            .collect();
+        Ok(Self { path, entries })
+    }
+
+    pub fn allowed(&self, file: &Path, hash: &str) -> bool {
+        self.entries.iter().any(|(h, f)| h == hash && f == file)
+    }
+
+    /// replaces whatever the file was allowed with before
+    pub fn allow(&mut self, file: &Path, hash: String) -> Result<()> {
+        self.entries.retain(|(_, f)| f != file);
+        self.entries.push((hash, file.to_path_buf()));
+        let mut text = String::default();
+        for (hash, file) in &self.entries {
+            writeln!(text, "{hash} {}", file.display())?;
+        }
+        fs::create_dir_all(self.path.parent().unwrap())?;
+        fs::write(&self.path, text)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    /// hash of the paths and contents of every file an env is read from, so
+    /// changing any of them needs allowing again
+    pub fn hash(paths: &[PathBuf]) -> Result<String> {
+        let mut hasher = Sha256::new();
+        for path in paths {
+            let content =
+                fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(content.len().to_le_bytes());
+            hasher.update(&content);
+        }
+        let mut result = String::default();
+        for byte in hasher.finalize() {
+            write!(result, "{byte:02x}")?;
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use tempfile::tempdir;
+
+use envee::env::Resolver;
+
+/// resolves toml files with the given lines
+#[allow(dead_code)]
+pub fn resolve<F>(files: &[&[&str]], f: F) -> Result<Vec<(String, String)>>
+where
+    F: FnOnce(Resolver) -> Resolver,
+{
+    let files: Vec<_> = files
+        .iter()
+        .enumerate()
+        .map(|(i, lines)| (format!("test-{i}.toml"), *lines))
+        .collect();
+    resolve_named(&files, f)
+}
+
+/// resolves files with the given names and lines
+pub fn resolve_named<F, N>(files: &[(N, &[&str])], f: F) -> Result<Vec<(String, String)>>
+where
+    F: FnOnce(Resolver) -> Resolver,
+    N: AsRef<str>,
+{
+    resolve_dir(files, |root| {
+        let paths = files.iter().map(|(name, _)| root.join(name.as_ref()));
+        f(Resolver::new(paths.collect()))
+    })
+}
+
+/// writes files with the given names and lines into a directory, then
+/// resolves using the resolver built from that directory
+pub fn resolve_dir<F, N>(files: &[(N, &[&str])], f: F) -> Result<Vec<(String, String)>>
+where
+    F: FnOnce(&Path) -> Resolver,
+    N: AsRef<str>,
+{
+    let root = tempdir().unwrap();
+    assert!(root.path().is_dir());
+    for (name, lines) in files {
+        let path = root.path().join(name.as_ref());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut file = File::create(&path).unwrap();
+        for line in lines.iter() {
+            writeln!(file, "{line}").unwrap();
+        }
+    }
+    f(root.path()).get()
+}
+
+pub fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// first line of an error, without the locations of a diagnostic
+#[allow(dead_code)]
+pub fn message(error: &anyhow::Error) -> String {
+    error.to_string().lines().next().unwrap().to_string()
+}
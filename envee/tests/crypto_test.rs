@@ -0,0 +1,263 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tempfile::tempdir;
+
+use common::{message, pairs, resolve};
+use envee::crypto::Key;
+
+#[test]
+fn test_round_trip() {
+    let root = tempdir().unwrap();
+    let file = key_file(root.path(), "key");
+    let key = Key::load(Some(&file), "").unwrap().unwrap();
+    let encrypted = key.encrypt("value", "s3cr3t $HOME\n").unwrap();
+    assert_ne!(key.encrypt("value", "s3cr3t $HOME\n").unwrap(), encrypted);
+    assert_eq!("s3cr3t $HOME\n", key.decrypt("value", &encrypted).unwrap());
+    unsafe {
+        std::env::set_var("ENVEE_TEST_PASSPHRASE", "correct horse");
+        std::env::set_var("ENVEE_TEST_OTHER_PASSPHRASE", "battery staple");
+    }
+    let passphrase = Key::load(None, "ENVEE_TEST_PASSPHRASE").unwrap().unwrap();
+    let encrypted = passphrase.encrypt("value", "s3cr3t").unwrap();
+    // derived again from the passphrase and the stored salt
+    let again = Key::load(None, "ENVEE_TEST_PASSPHRASE").unwrap().unwrap();
+    assert_eq!("s3cr3t", again.decrypt("value", &encrypted).unwrap());
+    assert!(
+        Key::load(None, "ENVEE_TEST_UNSET_PASSPHRASE")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_errors() {
+    let root = tempdir().unwrap();
+    let key = Key::load(Some(&key_file(root.path(), "a")), "")
+        .unwrap()
+        .unwrap();
+    let other = Key::load(Some(&key_file(root.path(), "b")), "")
+        .unwrap()
+        .unwrap();
+    let encrypted = key.encrypt("value", "s3cr3t").unwrap();
+    assert!(other.decrypt("value", &encrypted).is_err());
+    let mut tampered = encrypted.into_bytes();
+    let last = tampered.len() - 3;
+    tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+    assert!(
+        key.decrypt("value", &String::from_utf8(tampered).unwrap())
+            .is_err()
+    );
+    assert!(key.decrypt("value", "plain text").is_err());
+    // the path is authenticated along with the value
+    let encrypted = key.encrypt("db.password", "s3cr3t").unwrap();
+    assert!(key.decrypt("api.token", &encrypted).is_err());
+    unsafe {
+        std::env::set_var("ENVEE_TEST_ERRORS_PASSPHRASE", "correct horse");
+    }
+    let passphrase = Key::load(None, "ENVEE_TEST_ERRORS_PASSPHRASE")
+        .unwrap()
+        .unwrap();
+    let error = passphrase
+        .decrypt("value", &key.encrypt("value", "s3cr3t").unwrap())
+        .unwrap_err();
+    assert_eq!("value was encrypted with a key file", error.to_string());
+    fs::write(root.path().join("invalid"), "not a key").unwrap();
+    assert!(Key::load(Some(&root.path().join("invalid")), "").is_err());
+}
+
+#[test]
+fn test_resolve() {
+    let root = tempdir().unwrap();
+    let file = key_file(root.path(), "key");
+    let key = Key::load(Some(&file), "").unwrap().unwrap();
+    let line = format!(
+        "password = {{ envee.encrypted = \"{}\" }}",
+        key.encrypt("password", "p$ss").unwrap()
+    );
+    let lines = [line.as_str(), "url = \"db://${PASSWORD}@host\""];
+    assert_eq!(
+        pairs(&[("PASSWORD", "p$ss"), ("URL", "db://p$ss@host")]),
+        resolve(&[&lines], |resolver| resolver
+            .key(Key::load(Some(&file), "").unwrap()))
+        .unwrap(),
+    );
+    let error = resolve(&[&lines], |resolver| resolver).unwrap_err();
+    assert_eq!(
+        "PASSWORD is encrypted but no key was given",
+        message(&error)
+    );
+    // a value copied to another key does not decrypt
+    let swapped = line.replacen("password", "api_token", 1);
+    let error = resolve(&[&[&swapped]], |resolver| {
+        resolver.key(Key::load(Some(&file), "").unwrap())
+    })
+    .unwrap_err();
+    assert!(message(&error).starts_with("failed to decrypt API_TOKEN"));
+    // only the envee table marks an encrypted value
+    let lines = [
+        "token = { encrypted = \"yes\" }",
+        "[disk]",
+        "encrypted = \"yes\"",
+        "secret = true",
+    ];
+    assert_eq!(
+        pairs(&[
+            ("TOKEN_ENCRYPTED", "yes"),
+            ("DISK_ENCRYPTED", "yes"),
+            ("DISK_SECRET", "true"),
+        ]),
+        resolve(&[&lines], |resolver| resolver).unwrap(),
+    );
+}
+
+#[test]
+fn test_commands() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(
+        &path,
+        "[db]\npassword = \"p$$ss\" # keep\n\n[stages.prod]\ntoken = \"t\"\n",
+    )
+    .unwrap();
+    let file = path.to_str().unwrap();
+    let key = root.path().join("keys/key");
+    let key = key.to_str().unwrap();
+    let new = root.path().join("keys/new");
+    let new = new.to_str().unwrap();
+    envee(&["keygen", key]);
+    envee(&["keygen", new]);
+    let status = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["keygen", key])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+    envee(&["encrypt", "-f", file, "--key-file", key, "DB_PASSWORD"]);
+    envee(&[
+        "encrypt",
+        "-f",
+        file,
+        "--key-file",
+        key,
+        "-s",
+        "prod",
+        "TOKEN",
+    ]);
+    envee(&[
+        "encrypt",
+        "-f",
+        file,
+        "--key-file",
+        key,
+        "-s",
+        "prod",
+        "API",
+        "--value",
+        "a$i",
+    ]);
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(!text.contains("p$$ss") && !text.contains("\"t\""));
+    assert!(text.contains("password = { envee.encrypted = \"") && text.contains(" # keep\n"));
+    let expected = "export DB_PASSWORD='p$ss'\nexport TOKEN='t'\nexport API='a$i'\n";
+    assert_eq!(
+        expected,
+        envee(&[
+            "show",
+            "-f",
+            file,
+            "-s",
+            "prod",
+            "--key-file",
+            key,
+            "--reveal"
+        ])
+    );
+    envee(&[
+        "rotate",
+        "-f",
+        file,
+        "--key-file",
+        key,
+        "--new-key-file",
+        new,
+    ]);
+    assert_eq!(
+        expected,
+        envee(&[
+            "show",
+            "-f",
+            file,
+            "-s",
+            "prod",
+            "--key-file",
+            new,
+            "--reveal"
+        ])
+    );
+    envee(&["decrypt", "-f", file, "--key-file", new]);
+    assert_eq!(
+        "[db]\npassword = \"p$$ss\" # keep\n\n[stages.prod]\ntoken = \"t\"\nAPI = \"a$$i\"\n",
+        fs::read_to_string(&path).unwrap()
+    );
+}
+
+#[test]
+fn test_attributes() {
+    let root = tempdir().unwrap();
+    let key = key_file(root.path(), "key");
+    let new = key_file(root.path(), "new");
+    let encrypted = Key::load(Some(&key), "")
+        .unwrap()
+        .unwrap()
+        .encrypt("db.password", "p$ss")
+        .unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(
+        &path,
+        format!("[db]\npassword = {{ envee.encrypted = \"{encrypted}\", envee.secret = true }}\n"),
+    )
+    .unwrap();
+    let file = path.to_str().unwrap();
+    let (key, new) = (key.to_str().unwrap(), new.to_str().unwrap());
+    envee(&[
+        "rotate",
+        "-f",
+        file,
+        "--key-file",
+        key,
+        "--new-key-file",
+        new,
+    ]);
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(!text.contains(&encrypted) && text.contains(", envee.secret = true }"));
+    assert_eq!(
+        "export DB_PASSWORD='p$ss'\n",
+        envee(&["show", "-f", file, "--key-file", new, "--reveal"])
+    );
+    envee(&["decrypt", "-f", file, "--key-file", new]);
+    assert_eq!(
+        "[db]\npassword = { envee.value = \"p$$ss\", envee.secret = true }\n",
+        fs::read_to_string(&path).unwrap()
+    );
+}
+
+fn envee(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(args)
+        .env_remove("ENVEE_KEY_FILE")
+        .env_remove("ENVEE_PASSPHRASE")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn key_file(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, Key::generate().unwrap()).unwrap();
+    path
+}
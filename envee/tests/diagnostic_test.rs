@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::PathBuf;
+
+use tempfile::tempdir;
+
+use envee::env::Resolver;
+
+#[test]
+fn test_parse() {
+    test(
+        &[("test.toml", "name = \"app\"\nport = = 1\n")],
+        &[
+            "extra `=`, expected nothing",
+            "  --> {0}:2:8",
+            "   |",
+            " 2 | port = = 1",
+            "   |        ^",
+        ],
+    );
+    test(
+        &[("test.json", "{\n  \"name\": app\n}\n")],
+        &[
+            "expected value",
+            "  --> {0}:2:11",
+            "   |",
+            " 2 |   \"name\": app",
+            "   |           ^",
+        ],
+    );
+    test(
+        &[(".env", "NAME=app\nURL=\"http://\n")],
+        &[
+            "unterminated double quoted value",
+            "  --> {0}:2:5",
+            "   |",
+            " 2 | URL=\"http://",
+            "   |     ^",
+        ],
+    );
+}
+
+#[test]
+fn test_array() {
+    test(
+        &[("test.toml", "[db]\nhosts = [[\"a\"], \"b\"]\n")],
+        &[
+            "toml arrays must contain only scalars or only tables: db.hosts",
+            "  --> {0}:2:1",
+            "   |",
+            " 2 | hosts = [[\"a\"], \"b\"]",
+            "   | ^",
+        ],
+    );
+}
+
+#[test]
+fn test_duplicate() {
+    test(
+        &[
+            ("base.toml", "[db]\nhost = \"localhost\"\n"),
+            ("local.env", "# local\n\tDB_HOST=remote\n"),
+        ],
+        &[
+            "duplicate environment variable: DB_HOST",
+            "  --> {0}:2:1 (db.host)",
+            "   |",
+            " 2 | host = \"localhost\"",
+            "   | ^ first defined here",
+            "  --> {1}:2:2 (DB_HOST)",
+            "   |",
+            " 2 | \tDB_HOST=remote",
+            "   | \t^ defined again here, use --override to let it win",
+        ],
+    );
+}
+
+#[test]
+fn test_required() {
+    test(
+        &[(
+            "test.toml",
+            "a = \"${B}\"\n\n[b]\nenvee.value = \"${UNSET_ENVEE_VARIABLE:?needed}\"\n",
+        )],
+        &[
+            "UNSET_ENVEE_VARIABLE: needed",
+            "  --> {0}:3:2 (b)",
+            "   |",
+            " 3 | [b]",
+            "   |  ^",
+        ],
+    );
+}
+
+#[test]
+fn test_collision() {
+    test(
+        &[("test.toml", "[a]\nb_c = 1\n\n[a_b]\nc = 2\n")],
+        &[
+            "key collision: A_B_C",
+            "  --> {0}:2:1 (a.b_c)",
+            "   |",
+            " 2 | b_c = 1",
+            "   | ^ becomes A_B_C",
+            "  --> {0}:5:1 (a_b.c)",
+            "   |",
+            " 5 | c = 2",
+            "   | ^ also becomes A_B_C",
+        ],
+    );
+    test(
+        &[("test.toml", "db-host = \"localhost\"\n")],
+        &[
+            "invalid environment variable name: DB-HOST",
+            "  --> {0}:1:1 (db-host)",
+            "   |",
+            " 1 | db-host = \"localhost\"",
+            "   | ^ names may only contain letters, digits and underscores and cannot start with a digit",
+        ],
+    );
+}
+
+/// writes the files and compares the error, `{i}` is replaced with the
+/// path of the i-th file
+fn test(files: &[(&str, &str)], expected: &[&str]) {
+    let root = tempdir().unwrap();
+    let paths: Vec<PathBuf> = files
+        .iter()
+        .map(|(name, text)| {
+            let path = root.path().join(name);
+            fs::write(&path, text).unwrap();
+            path
+        })
+        .collect();
+    let error = Resolver::new(paths.clone()).get().unwrap_err();
+    let mut expected = expected.join("\n");
+    for (i, path) in paths.iter().enumerate() {
+        let path = fs::canonicalize(path).unwrap();
+        expected = expected.replace(&format!("{{{i}}}"), &path.display().to_string());
+    }
+    assert_eq!(expected, error.to_string());
+}
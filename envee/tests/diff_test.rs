@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::json;
+use tempfile::tempdir;
+
+#[test]
+fn test_stages() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    let (code, output) = diff(root.path(), &["-f", "envee.toml", "--stage-b", "prod"]);
+    assert_eq!(Some(1), code);
+    assert_eq!(
+        "~ HOST = \"localhost\" -> \"prod.example.com\"\n~ API_TOKEN = \"****\" -> \"****\"\n",
+        output
+    );
+    let (code, output) = diff(
+        root.path(),
+        &[
+            "-f",
+            "envee.toml",
+            "--stage-a",
+            "prod",
+            "-s",
+            "prod",
+            "--reveal",
+        ],
+    );
+    assert_eq!((Some(0), ""), (code, output.as_str()));
+}
+
+#[test]
+fn test_files() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    let (code, output) = diff(
+        root.path(),
+        &["-a", "envee.toml", "-b", "other.toml", "--reveal"],
+    );
+    assert_eq!(Some(1), code);
+    assert_eq!(
+        "- HOST = \"localhost\"\n- API_TOKEN = \"a\"\n+ NEW = \"y\"\n",
+        output
+    );
+    let (code, output) = diff(
+        root.path(),
+        &["-a", "envee.toml", "-b", "other.toml", "--json"],
+    );
+    assert_eq!(Some(1), code);
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let expected = json!({
+        "added": { "NEW": "y" },
+        "removed": { "HOST": "localhost", "API_TOKEN": "****" },
+        "changed": {},
+    });
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn test_override() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .current_dir(root.path())
+        .args(["diff", "-a", "envee.toml", "-a", "other.toml", "--override"])
+        .args(["-b", "envee.toml", "-b", "other.toml", "--reveal"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(
+        "warning: overridden environment variables: NAME\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+}
+
+#[test]
+fn test_error() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    fs::write(
+        root.path().join("broken.toml"),
+        "name = \"${UNSET_ENVEE_VARIABLE:?}\"\n",
+    )
+    .unwrap();
+    for args in [
+        &["-a", "broken.toml", "-b", "envee.toml"][..],
+        &["-a", "envee.toml", "-b", "broken.toml"],
+        &["-a", "envee.toml", "-b", "missing.toml"],
+        &["-f", "envee.toml", "--stage-b", "missing"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+            .current_dir(root.path())
+            .env("RUST_BACKTRACE", "0")
+            .arg("diff")
+            .args(args)
+            .output()
+            .unwrap();
+        assert_eq!(Some(2), output.status.code(), "{output:?}");
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("Error: "), "{stderr}");
+    }
+}
+
+fn write(dir: &Path) {
+    fs::write(
+        dir.join("envee.toml"),
+        "name = \"app\"\nhost = \"localhost\"\napi_token = \"a\"\n\n[stages.prod]\nhost = \"prod.example.com\"\napi_token = \"b\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("other.toml"), "name = \"app\"\nnew = \"y\"\n").unwrap();
+}
+
+fn diff(dir: &Path, args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .current_dir(dir)
+        .arg("diff")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.stderr.is_empty(), "{output:?}");
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
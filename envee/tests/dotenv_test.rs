@@ -0,0 +1,98 @@
+mod common;
+
+use common::{message, pairs, resolve, resolve_named};
+use envee::env::Format;
+
+#[test]
+fn test_basic() {
+    test(
+        &[
+            "# comment",
+            "",
+            "NAME=john",
+            "export AGE = 75",
+            "  SPACED=  some value  ",
+            "COMMENTED=value # comment",
+            "HASH=value#not-comment",
+            "EMPTY=",
+        ],
+        &[
+            ("NAME", "john"),
+            ("AGE", "75"),
+            ("SPACED", "some value"),
+            ("COMMENTED", "value"),
+            ("HASH", "value#not-comment"),
+            ("EMPTY", ""),
+        ],
+    );
+}
+
+#[test]
+fn test_quotes() {
+    test(
+        &[
+            "NAME=john",
+            "SINGLE='${NAME} \\n # $$'",
+            "DOUBLE=\"${NAME} \\\"quoted\\\" \\$NAME \\\\ \\t\" # comment",
+            "MULTI=\"first",
+            "second\"",
+            "LITERAL='first",
+            "second'",
+        ],
+        &[
+            ("NAME", "john"),
+            ("SINGLE", "${NAME} \\n # $$"),
+            ("DOUBLE", "john \"quoted\" $NAME \\ \t"),
+            ("MULTI", "first\nsecond"),
+            ("LITERAL", "first\nsecond"),
+        ],
+    );
+}
+
+#[test]
+fn test_errors() {
+    for lines in [
+        &["NAME"][..],
+        &["=value"],
+        &["NAME=\"unterminated"],
+        &["NAME='unterminated"],
+        &["NAME=\"value\" trailing"],
+    ] {
+        assert!(resolve_named(&[(".env", lines)], |resolver| resolver).is_err());
+    }
+}
+
+#[test]
+fn test_mixed() {
+    let files: [(&str, &[&str]); 2] = [
+        ("base.toml", &["db.host = \"localhost\""]),
+        ("local.env", &["DB_URL=postgres://${DB_HOST}"]),
+    ];
+    assert_eq!(
+        pairs(&[("DB_HOST", "localhost"), ("DB_URL", "postgres://localhost")]),
+        resolve_named(&files, |resolver| resolver).unwrap(),
+    );
+    let files: [(&str, &[&str]); 2] = [
+        ("base.env", &["DB_HOST=localhost"]),
+        ("local.env", &["DB_HOST=remote"]),
+    ];
+    let error = resolve_named(&files, |resolver| resolver).unwrap_err();
+    assert_eq!("duplicate environment variable: DB_HOST", message(&error));
+}
+
+#[test]
+fn test_format() {
+    let lines = ["NAME=john"];
+    assert!(resolve(&[&lines], |resolver| resolver).is_err());
+    assert_eq!(
+        pairs(&[("NAME", "john")]),
+        resolve(&[&lines], |resolver| resolver.format(Some(Format::Dotenv))).unwrap(),
+    );
+}
+
+fn test(lines: &[&str], expected: &[(&str, &str)]) {
+    for name in [".env", ".env.local", "test.env"] {
+        let actual = resolve_named(&[(name, lines)], |resolver| resolver).unwrap();
+        assert_eq!(pairs(expected), actual);
+    }
+}
@@ -1,9 +1,6 @@
-use std::fs::File;
-use std::io::Write;
+mod common;
 
-use tempfile::tempdir;
-
-use envee::env::Resolver;
+use common::{message, pairs, resolve};
 
 #[test]
 fn test_basic() {
@@ -37,6 +34,147 @@ fn test_nested() {
     );
 }
 
+#[test]
+fn test_arrays() {
+    test(
+        &[
+            "hosts = [\"a\", \"b\", \"c\"]",
+            "ports = [80, 443]",
+            "empty = []",
+            "path = { envee.value = [\"/usr/bin\", \"/bin\"], envee.join = \":\" }",
+            "[[servers]]",
+            "host = \"a\"",
+            "tags = [\"x\", \"y\"]",
+            "[[servers]]",
+            "host = \"b\"",
+        ],
+        &[
+            ("HOSTS", "a,b,c"),
+            ("PORTS", "80,443"),
+            ("EMPTY", ""),
+            ("PATH", "/usr/bin:/bin"),
+            ("SERVERS_0_HOST", "a"),
+            ("SERVERS_0_TAGS", "x,y"),
+            ("SERVERS_1_HOST", "b"),
+        ],
+    );
+    test(
+        &[
+            "[envee]",
+            "join = \" \"",
+            "[flags]",
+            "value = [\"a\", \"b\"]",
+            "other = [1, 2]",
+        ],
+        &[("FLAGS_VALUE", "a b"), ("FLAGS_OTHER", "1 2")],
+    );
+    // only the envee table marks a value with attributes
+    test(
+        &["[db]", "value = 3", "[log]", "value = 1", "join = \"-\""],
+        &[("DB_VALUE", "3"), ("LOG_VALUE", "1"), ("LOG_JOIN", "-")],
+    );
+}
+
+#[test]
+fn test_array_errors() {
+    for lines in [
+        &["nested = [[1, 2], [3]]"][..],
+        &["mixed = [1, { a = 1 }]"],
+        &["key = { envee.value = [1], envee.join = 1 }"],
+        &["key = { envee.value = [1], other = 1 }"],
+        &["[envee]", "unknown = 1"],
+    ] {
+        assert!(resolve(&[lines], |resolver| resolver).is_err());
+    }
+}
+
+#[test]
+fn test_collision() {
+    for lines in [
+        &["a.b_c = 1", "a_b.c = 2"][..],
+        &["Name = 1", "name = 2"],
+        &["list = [{ a = 1 }]", "list_0_a = 2"],
+        &["[stages.prod]", "Name = 1", "name = 2"],
+    ] {
+        let error = resolve(&[lines], |resolver| resolver.stage(Some("prod".into())));
+        assert!(message(&error.unwrap_err()).starts_with("key collision: "));
+    }
+    assert_eq!(
+        pairs(&[("NAME", "2")]),
+        resolve(&[&["name = 1", "[stages.prod]", "Name = 2"]], |resolver| {
+            resolver.stage(Some("prod".into()))
+        })
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_names() {
+    for lines in [
+        &["db-host = 1"][..],
+        &["\"db.host\" = 1"],
+        &["1password = 1"],
+        &["[0]", "a = 1"],
+        &["\"\" = 1"],
+    ] {
+        let error = resolve(&[lines], |resolver| resolver).unwrap_err();
+        assert!(message(&error).starts_with("invalid environment variable name: "));
+    }
+    test(
+        &["_private = 1", "[db]", "host2 = 2"],
+        &[("_PRIVATE", "1"), ("DB_HOST2", "2")],
+    );
+}
+
+#[test]
+fn test_naming() {
+    test(
+        &[
+            "[envee]",
+            "separator = \"__\"",
+            "case = \"preserve\"",
+            "prefix = \"MYAPP_\"",
+            "[Logging.LogLevel]",
+            "Default = \"info\"",
+            "[[Kestrel]]",
+            "Url = \"http://a\"",
+        ],
+        &[
+            ("MYAPP_Logging__LogLevel__Default", "info"),
+            ("MYAPP_Kestrel__0__Url", "http://a"),
+        ],
+    );
+    test(
+        &[
+            "[database]",
+            "host = \"db\"",
+            "[logging]",
+            "envee = { nest = false }",
+            "level = \"info\"",
+            "[dotnet]",
+            "envee = { separator = \"__\", case = \"preserve\" }",
+            "Logging = { LogLevel = \"debug\" }",
+            "[lower]",
+            "envee.case = \"lower\"",
+            "Key = [{ A = 1 }]",
+        ],
+        &[
+            ("DATABASE_HOST", "db"),
+            ("LEVEL", "info"),
+            ("DOTNET__Logging__LogLevel", "debug"),
+            ("LOWER_key_0_a", "1"),
+        ],
+    );
+    for lines in [
+        &["[envee]", "case = \"title\""][..],
+        &["[envee]", "nest = false"],
+        &["[table]", "envee = { prefix = \"A_\" }"],
+        &["[table]", "envee = 1"],
+    ] {
+        assert!(resolve(&[lines], |resolver| resolver).is_err());
+    }
+}
+
 #[test]
 fn test_expansion() {
     unsafe {
@@ -54,24 +192,145 @@ fn test_expansion() {
             ("A", "a"),
             ("B", "ab"),
             ("C", "abc"),
-            ("D", "d"),
+            ("D", "abced"),
             ("E", "abce"),
         ],
     );
+    // a variable referencing itself extends the host variable
+    let path = std::env::var("PATH").unwrap();
+    test(
+        &["path = \"/bin:${PATH}\"", "unset = \"${UNSET}x\""],
+        &[("PATH", &format!("/bin:{path}")), ("UNSET", "x")],
+    );
+}
+
+#[test]
+fn test_operators() {
+    test(
+        &[
+            "empty = \"\"",
+            "name = \"app\"",
+            "a = \"${UNSET_ENVEE_VARIABLE:-fallback}\"",
+            "b = \"${EMPTY:-fallback}\"",
+            "c = \"${EMPTY-fallback}\"",
+            "d = \"${NAME:-fallback}\"",
+            "e = \"${UNSET_ENVEE_VARIABLE:-${NAME}-${EMPTY:+x}}\"",
+            "f = \"${NAME:+alternate}\"",
+            "g = \"${EMPTY:+alternate}\"",
+            "h = \"${EMPTY+alternate}\"",
+            "i = \"${NAME:?name is required}\"",
+            "j = \"$5 $ $$NAME ${LATER}\"",
+            "later = \"value\"",
+        ],
+        &[
+            ("EMPTY", ""),
+            ("NAME", "app"),
+            ("A", "fallback"),
+            ("B", "fallback"),
+            ("C", ""),
+            ("D", "app"),
+            ("E", "app-"),
+            ("F", "alternate"),
+            ("G", ""),
+            ("H", "alternate"),
+            ("I", "app"),
+            ("J", "$5 $ $NAME value"),
+            ("LATER", "value"),
+        ],
+    );
+}
+
+#[test]
+fn test_required() {
+    let lines = ["password = \"${UNSET_ENVEE_VARIABLE:?set the db password}\""];
+    let error = resolve(&[&lines], |resolver| resolver).unwrap_err();
+    assert_eq!("UNSET_ENVEE_VARIABLE: set the db password", message(&error));
+    let lines = ["password = \"${UNSET_ENVEE_VARIABLE:?}\""];
+    let error = resolve(&[&lines], |resolver| resolver).unwrap_err();
+    assert_eq!(
+        "UNSET_ENVEE_VARIABLE: parameter null or not set",
+        message(&error)
+    );
+    let lines = ["password = \"${UNSET_ENVEE_VARIABLE\""];
+    assert!(resolve(&[&lines], |resolver| resolver).is_err());
+}
+
+#[test]
+fn test_cycle() {
+    let lines = ["a = \"${B}\"", "b = \"${C}\"", "c = \"${A}\""];
+    let error = resolve(&[&lines], |resolver| resolver).unwrap_err();
+    assert_eq!("reference cycle: A -> B -> C -> A", message(&error));
+    let lines = ["a = \"${B}\"", "b = \"${A}${B}\""];
+    let error = resolve(&[&lines], |resolver| resolver).unwrap_err();
+    assert_eq!("reference cycle: A -> B -> A", message(&error));
+}
+
+#[test]
+fn test_stage() {
+    let lines = [
+        "name = \"app\"",
+        "db.host = \"localhost\"",
+        "[stages.dev]",
+        "debug = true",
+        "[stages.prod]",
+        "db.host = \"db.internal\"",
+        "replicas = 3",
+    ];
+    assert_eq!(
+        pairs(&[("NAME", "app"), ("DB_HOST", "localhost")]),
+        resolve(&[&lines], |resolver| resolver).unwrap(),
+    );
+    assert_eq!(
+        pairs(&[("NAME", "app"), ("DB_HOST", "localhost"), ("DEBUG", "true")]),
+        resolve(&[&lines], |resolver| resolver.stage(Some("dev".into()))).unwrap(),
+    );
+    assert_eq!(
+        pairs(&[
+            ("NAME", "app"),
+            ("DB_HOST", "db.internal"),
+            ("REPLICAS", "3")
+        ]),
+        resolve(&[&lines], |resolver| resolver.stage(Some("prod".into()))).unwrap(),
+    );
+}
+
+#[test]
+fn test_stage_missing() {
+    let files: [&[&str]; 2] = [&["name = \"app\""], &["[stages.dev]", "debug = true"]];
+    assert!(resolve(&files, |resolver| resolver.stage(Some("dev".into()))).is_ok());
+    let error = resolve(&files, |resolver| resolver.stage(Some("prod".into()))).unwrap_err();
+    assert_eq!("stage not found: prod", message(&error));
+}
+
+#[test]
+fn test_override() {
+    let files: [&[&str]; 2] = [
+        &[
+            "host = \"db.internal\"",
+            "port = 5432",
+            "url = \"${HOST}:${PORT}\"",
+        ],
+        &["host = \"localhost\""],
+    ];
+    let error = resolve(&files, |resolver| resolver).unwrap_err();
+    assert_eq!("duplicate environment variable: HOST", message(&error));
+    assert_eq!(
+        pairs(&[
+            ("HOST", "localhost"),
+            ("PORT", "5432"),
+            ("URL", "localhost:5432"),
+        ]),
+        resolve(&files, |resolver| resolver.overrides(true)).unwrap(),
+    );
+    // a variable referencing itself extends the definition it replaces
+    let files: [&[&str]; 3] = [&["a = \"x\""], &["a = \"${A}y\""], &["a = \"${A}z\""]];
+    assert_eq!(
+        pairs(&[("A", "xyz")]),
+        resolve(&files, |resolver| resolver.overrides(true)).unwrap(),
+    );
 }
 
 fn test(lines: &[&str], expected: &[(&str, &str)]) {
-    let root = tempdir().unwrap();
-    assert!(root.path().is_dir());
-    let path = root.path().join("test.toml");
-    let mut file = File::create(&path).unwrap();
-    for line in lines {
-        writeln!(file, "{line}").unwrap();
-    }
-    let actual = Resolver::new(vec![path]).get().unwrap();
-    let expected: Vec<_> = expected
-        .iter()
-        .map(|(key, value)| (key.to_string(), value.to_string()))
-        .collect();
-    assert_eq!(expected, actual);
+    let actual = resolve(&[lines], |resolver| resolver).unwrap();
+    assert_eq!(pairs(expected), actual);
 }
@@ -0,0 +1,80 @@
+use std::fs;
+use std::process::Command;
+
+use tempfile::tempdir;
+
+use envee::env::{Resolver, Source};
+
+#[test]
+fn test_origin() {
+    let root = tempdir().unwrap();
+    let toml = root.path().join("test.toml");
+    let dotenv = root.path().join(".env");
+    fs::write(
+        &toml,
+        "name = \"app\"\n\n[database]\nurl = \"${HOST}/${NAME:-${OTHER}}\"\n\n[stages.prod]\nname = \"prod\"\n",
+    )
+    .unwrap();
+    fs::write(&dotenv, "# comment\nexport HOST=localhost\n").unwrap();
+    let variables = Resolver::new(vec![toml.clone(), dotenv.clone()])
+        .stage(Some("prod".into()))
+        .resolve()
+        .unwrap();
+    let origins: Vec<_> = variables
+        .iter()
+        .map(|variable| {
+            let origin = &variable.origin;
+            (variable.key.as_str(), origin.path.as_str(), origin.position)
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ("NAME", "stages.prod.name", Some((7, 1))),
+            ("DATABASE_URL", "database.url", Some((4, 1))),
+            ("HOST", "HOST", Some((2, 8))),
+        ],
+        origins
+    );
+    let url = &variables[1];
+    assert_eq!("localhost/prod", url.value);
+    assert_eq!(
+        Source::Template("${HOST}/${NAME:-${OTHER}}".into()),
+        url.source
+    );
+    assert_eq!(vec!["HOST", "NAME", "OTHER"], url.references);
+    let toml = fs::canonicalize(&toml).unwrap();
+    assert_eq!(format!("{}:4:1", toml.display()), url.origin.to_string());
+}
+
+#[test]
+fn test_explain() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "host = \"localhost\"\nurl = \"http://${HOST}\"\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["explain", "-f"])
+        .arg(&path)
+        .arg("URL")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let path = fs::canonicalize(&path).unwrap();
+    let expected = format!(
+        "URL = \"http://localhost\"\n  from {0}:2:1 (url)\n  template \"http://${{HOST}}\"\n  HOST = \"localhost\"\n    from {0}:1:1 (host)\n",
+        path.display()
+    );
+    assert_eq!(expected, String::from_utf8(output.stdout).unwrap());
+    fs::write(&path, "path = \"/bin:${PATH}\"\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["explain", "-f"])
+        .arg(&path)
+        .arg("PATH")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let host = std::env::var("PATH").unwrap();
+    assert!(
+        stdout.ends_with(&format!("\n  PATH = {host:?} (host environment)\n")),
+        "{stdout}"
+    );
+}
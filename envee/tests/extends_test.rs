@@ -0,0 +1,83 @@
+mod common;
+
+use common::{message, pairs, resolve_dir};
+use envee::env::Resolver;
+
+#[test]
+fn test_extends() {
+    let files: [(&str, &[&str]); 3] = [
+        ("base.toml", &["name = \"app\"", "log = \"info\""]),
+        (
+            "services/api.toml",
+            &["extends = \"../base.toml\"", "url = \"http://${NAME}\""],
+        ),
+        (
+            "services/worker.toml",
+            &["extends = [\"../base.toml\", \"queue.env\"]", "workers = 4"],
+        ),
+    ];
+    assert_eq!(
+        pairs(&[("NAME", "app"), ("LOG", "info"), ("URL", "http://app")]),
+        resolve_dir(&files, |root| Resolver::new(vec![
+            root.join("services/api.toml")
+        ]))
+        .unwrap(),
+    );
+    let mut files = files.to_vec();
+    files.push(("services/queue.env", &["QUEUE=jobs"]));
+    assert_eq!(
+        pairs(&[
+            ("NAME", "app"),
+            ("LOG", "info"),
+            ("QUEUE", "jobs"),
+            ("WORKERS", "4"),
+        ]),
+        resolve_dir(&files, |root| Resolver::new(vec![
+            root.join("services/worker.toml")
+        ]))
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_diamond() {
+    let files: [(&str, &[&str]); 4] = [
+        ("base.toml", &["name = \"app\""]),
+        ("a.toml", &["extends = \"base.toml\"", "a = 1"]),
+        ("b.toml", &["extends = \"base.toml\"", "b = 2"]),
+        ("main.toml", &["extends = [\"a.toml\", \"b.toml\"]"]),
+    ];
+    assert_eq!(
+        pairs(&[("NAME", "app"), ("A", "1"), ("B", "2")]),
+        resolve_dir(&files, |root| Resolver::new(vec![root.join("main.toml")])).unwrap(),
+    );
+}
+
+#[test]
+fn test_cycle() {
+    let files: [(&str, &[&str]); 3] = [
+        ("a.toml", &["extends = \"b.toml\""]),
+        ("b.toml", &["extends = \"c.toml\""]),
+        ("c.toml", &["extends = \"b.toml\""]),
+    ];
+    let error = resolve_dir(&files, |root| Resolver::new(vec![root.join("a.toml")])).unwrap_err();
+    let error = message(&error);
+    assert!(error.starts_with("include cycle: "), "{error}");
+    let chain: Vec<_> = error
+        .split(" -> ")
+        .map(|path| &path[path.len() - 6..])
+        .collect();
+    assert_eq!(vec!["b.toml", "c.toml", "b.toml"], chain);
+}
+
+#[test]
+fn test_missing() {
+    let files: [(&str, &[&str]); 1] = [("a.toml", &["extends = \"missing.toml\""])];
+    let error = resolve_dir(&files, |root| Resolver::new(vec![root.join("a.toml")])).unwrap_err();
+    let error = message(&error);
+    assert!(error.starts_with("failed to read "), "{error}");
+    assert!(
+        error.ends_with("a.toml -> missing.toml") || error.contains("a.toml -> "),
+        "{error}"
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{pairs, resolve_named};
+
+#[test]
+fn test_nested() {
+    let expected = [
+        ("NAME", "app"),
+        ("DEBUG", "true"),
+        ("SERVER_PORT", "8080"),
+        ("SERVER_RATIO", "0.5"),
+        ("SERVER_TLS_ENABLED", "false"),
+    ];
+    test(
+        &[
+            "name = \"app\"",
+            "debug = true",
+            "[server]",
+            "port = 8080",
+            "ratio = 0.5",
+            "tls.enabled = false",
+        ],
+        &[
+            "{",
+            "  \"name\": \"app\",",
+            "  \"debug\": true,",
+            "  \"server\": {",
+            "    \"port\": 8080,",
+            "    \"ratio\": 0.5,",
+            "    \"tls\": { \"enabled\": false }",
+            "  }",
+            "}",
+        ],
+        &[
+            "name: app",
+            "debug: true",
+            "server:",
+            "  port: 8080",
+            "  ratio: 0.5",
+            "  tls:",
+            "    enabled: false",
+        ],
+        &expected,
+    );
+}
+
+#[test]
+fn test_stage() {
+    let files: [(&str, &[&str]); 2] = [
+        (
+            "base.json",
+            &["{\"host\": \"localhost\", \"stages\": {\"prod\": {\"host\": \"db\"}}}"],
+        ),
+        ("prod.yml", &["stages:", "  prod:", "    replicas: 3"]),
+    ];
+    assert_eq!(
+        pairs(&[("HOST", "db"), ("REPLICAS", "3")]),
+        resolve_named(&files, |resolver| resolver.stage(Some("prod".into()))).unwrap(),
+    );
+}
+
+#[test]
+fn test_errors() {
+    let files: [(&str, &[&str]); 4] = [
+        ("null.json", &["{\"name\": null}"]),
+        ("list.json", &["[\"name\"]"]),
+        ("list.yaml", &["- name"]),
+        ("invalid.yaml", &["name: [app"]),
+    ];
+    for file in files {
+        assert!(resolve_named(&[file], |resolver| resolver).is_err());
+    }
+}
+
+fn test(toml: &[&str], json: &[&str], yaml: &[&str], expected: &[(&str, &str)]) {
+    for file in [
+        ("test.toml", toml),
+        ("test.json", json),
+        ("test.yaml", yaml),
+    ] {
+        let actual = resolve_named(&[file], |resolver| resolver).unwrap();
+        assert_eq!(pairs(expected), actual);
+    }
+}
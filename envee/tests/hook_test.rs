@@ -0,0 +1,66 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::process::Command;
+
+use tempfile::tempdir;
+
+#[test]
+fn test_hook() {
+    let root = tempdir().unwrap();
+    let project = root.path().join("project");
+    fs::create_dir_all(project.join("sub")).unwrap();
+    fs::write(
+        project.join("envee.toml"),
+        "name = \"app\"\nenvee_hook_host = \"project\"\n",
+    )
+    .unwrap();
+    let script = r#"
+envee="$1"
+eval "$("$envee" hook bash)"
+show() { echo "$1 ${NAME-unset} $ENVEE_HOOK_HOST"; }
+cd project/sub && _envee_hook && show untrusted
+"$envee" allow > /dev/null && _envee_hook && show allowed
+cd .. && _envee_hook && show parent
+cd .. && _envee_hook && show outside
+cd project && _envee_hook && show again
+printf 'name = "changed"\n' > envee.toml && _envee_hook && show changed
+"$envee" allow envee.toml > /dev/null && _envee_hook && show reallowed
+"#;
+    let output = Command::new("bash")
+        .current_dir(root.path())
+        .env("XDG_DATA_HOME", root.path().join("data"))
+        .env("ENVEE_HOOK_HOST", "host")
+        .env_remove("NAME")
+        .env_remove("ENVEE_LOADED")
+        .args(["-c", script, "bash", env!("CARGO_BIN_EXE_envee")])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        "untrusted unset host\nallowed app project\nparent app project\noutside unset host\nagain app project\nchanged unset host\nreallowed changed host\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let file = fs::canonicalize(project.join("envee.toml")).unwrap();
+    let file = file.display();
+    assert_eq!(
+        format!(
+            "envee: {file} is not allowed, run `envee allow` to load it\nenvee: loading {file}\nenvee: unloading {file}\nenvee: loading {file}\nenvee: {file} is not allowed, run `envee allow` to load it\nenvee: loading {file}\n"
+        ),
+        stderr
+    );
+}
+
+#[test]
+fn test_shells() {
+    for shell in ["bash", "zsh", "fish"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+            .args(["hook", shell])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let hook = String::from_utf8(output.stdout).unwrap();
+        assert!(hook.contains(&format!(" hook {shell} --apply")), "{hook}");
+    }
+}
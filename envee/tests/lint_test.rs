@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::tempdir;
+
+#[test]
+fn test_lint() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    let (code, output) = lint(root.path(), &[]);
+    assert_eq!(Some(1), code);
+    assert_eq!(
+        vec![
+            "warning[unused]: NAME is overridden, this definition is never used",
+            "warning[mixed-case]: apiKey has mixed case and becomes APIKEY",
+            "error[undefined]: URL references ENVEE_LINT_HOST, which is not defined",
+            "warning[empty]: EMPTY is empty",
+            "warning[shadowed]: ENVEE_LINT_SHADOWED shadows a variable of the host environment",
+            "found 1 error and 4 warnings",
+        ],
+        output
+    );
+}
+
+#[test]
+fn test_levels() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    let (code, output) = lint(
+        root.path(),
+        &["-A", "undefined,empty", "-W", "empty", "-D", "unused"],
+    );
+    assert_eq!(Some(1), code);
+    assert_eq!(
+        vec![
+            "error[unused]: NAME is overridden, this definition is never used",
+            "warning[mixed-case]: apiKey has mixed case and becomes APIKEY",
+            "warning[empty]: EMPTY is empty",
+            "warning[shadowed]: ENVEE_LINT_SHADOWED shadows a variable of the host environment",
+            "found 1 error and 3 warnings",
+        ],
+        output
+    );
+    let (code, output) = lint(
+        root.path(),
+        &["--allow", "undefined,mixed-case,empty,shadowed,unused"],
+    );
+    assert_eq!(Some(0), code);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_unresolved() {
+    let root = tempdir().unwrap();
+    fs::write(
+        root.path().join("a.toml"),
+        "password = { envee.encrypted = \"AQAAAA==\" }\ntoken = { envee.cmd = \"exit 1\" }\nurl = \"${PASSWORD}${TOKEN}\"\nhost = \"${ENVEE_LINT_HOST:?needed}\"\nempty = \"${PORT:+x}\"\n\n[schema]\nPORT = { required = true }\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .current_dir(root.path())
+        .env_remove("ENVEE_LINT_HOST")
+        .env_remove("PORT")
+        .env_remove("ENVEE_KEY_FILE")
+        .env_remove("ENVEE_PASSPHRASE")
+        .args(["lint", "-f", "a.toml", "-A", "shadowed"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("warning[empty]: EMPTY is empty\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.ends_with("found 0 errors and 1 warning\n"),
+        "{stdout}"
+    );
+}
+
+fn write(dir: &Path) {
+    fs::write(
+        dir.join("a.toml"),
+        "name = \"a\"\napiKey = \"k\"\nurl = \"http://${ENVEE_LINT_HOST}:${ENVEE_LINT_PORT:-80}/${NAME}\"\nempty = \"\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join(".env"), "NAME=b\nENVEE_LINT_SHADOWED=file\n").unwrap();
+}
+
+/// the lines of the messages without their locations
+fn lint(dir: &Path, args: &[&str]) -> (Option<i32>, Vec<String>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .current_dir(dir)
+        .env_remove("ENVEE_LINT_HOST")
+        .env("ENVEE_LINT_SHADOWED", "host")
+        .args(["lint", "-f", "a.toml", "-f", ".env", "--override"])
+        .args(args)
+        .output()
+        .unwrap();
+    let lines = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with(' '))
+        .map(str::to_string)
+        .collect();
+    (output.status.code(), lines)
+}
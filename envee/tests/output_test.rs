@@ -0,0 +1,134 @@
+mod common;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use common::{pairs, resolve_named};
+use envee::output::Format;
+
+#[test]
+fn test_lines() {
+    let env = pairs(&[("NAME", "app"), ("PORT", "8080")]);
+    test(
+        &env,
+        Format::Export,
+        &["export NAME='app'", "export PORT='8080'"],
+    );
+    test(
+        &env,
+        Format::Bash,
+        &["export NAME='app'", "export PORT='8080'"],
+    );
+    test(&env, Format::Dotenv, &["NAME=\"app\"", "PORT=\"8080\""]);
+    test(
+        &env,
+        Format::Fish,
+        &["set -gx NAME 'app'", "set -gx PORT '8080'"],
+    );
+    test(
+        &env,
+        Format::Powershell,
+        &["$env:NAME = 'app'", "$env:PORT = '8080'"],
+    );
+    test(&env, Format::Docker, &["NAME=app", "PORT=8080"]);
+    test(&env, Format::Systemd, &["NAME=\"app\"", "PORT=\"8080\""]);
+}
+
+#[test]
+fn test_json() {
+    let env = pairs(&[("NAME", "app"), ("PORT", "8080")]);
+    test(
+        &env,
+        Format::Json,
+        &["{", "  \"NAME\": \"app\",", "  \"PORT\": \"8080\"", "}"],
+    );
+}
+
+#[test]
+fn test_kubernetes() {
+    let env = pairs(&[("NAME", "app"), ("PORT", "8080")]);
+    test(
+        &env,
+        Format::Configmap,
+        &[
+            "apiVersion: v1",
+            "kind: ConfigMap",
+            "metadata:",
+            "  name: envee",
+            "data:",
+            "  NAME: app",
+            "  PORT: '8080'",
+        ],
+    );
+    let name = format!("  NAME: {}", STANDARD.encode("app"));
+    let port = format!("  PORT: {}", STANDARD.encode("8080"));
+    test(
+        &env,
+        Format::Secret,
+        &[
+            "apiVersion: v1",
+            "kind: Secret",
+            "metadata:",
+            "  name: envee",
+            "data:",
+            &name,
+            &port,
+        ],
+    );
+}
+
+#[test]
+fn test_escape() {
+    let env = pairs(&[("VALUE", "a \"b\" $c \\d `e`\nf")]);
+    test(
+        &env,
+        Format::Dotenv,
+        &["VALUE=\"a \\\"b\\\" \\$c \\\\d \\`e\\`\\nf\""],
+    );
+    test(
+        &env,
+        Format::Systemd,
+        &["VALUE=\"a \\\"b\\\" \\$c \\\\d \\`e\\`", "f\""],
+    );
+    assert!(Format::Docker.render(&env, "envee").is_err());
+}
+
+#[test]
+fn test_quote() {
+    let env = pairs(&[("VALUE", "it's $HOME\n\\")]);
+    test(
+        &env,
+        Format::Export,
+        &["export VALUE='it'\\''s $HOME", "\\'"],
+    );
+    test(&env, Format::Bash, &["export VALUE=$'it\\'s $HOME\\n\\\\'"]);
+    test(
+        &env,
+        Format::Fish,
+        &["set -gx VALUE 'it\\'s $HOME", "\\\\'"],
+    );
+    test(
+        &env,
+        Format::Powershell,
+        &["$env:VALUE = 'it''s $HOME", "\\'"],
+    );
+}
+
+#[test]
+fn test_dotenv_round_trip() {
+    let env = pairs(&[
+        ("PLAIN", "value"),
+        ("SPECIAL", "a \"b\" $c ${D} \\e `f` # g"),
+        ("MULTI", "first\nsecond"),
+        ("EMPTY", ""),
+    ]);
+    let text = Format::Dotenv.render(&env, "envee").unwrap();
+    let lines: Vec<_> = text.lines().collect();
+    let actual = resolve_named(&[(".env", &lines)], |resolver| resolver).unwrap();
+    assert_eq!(env, actual);
+}
+
+fn test(env: &[(String, String)], format: Format, expected: &[&str]) {
+    let actual = format.render(env, "envee").unwrap();
+    let expected: String = expected.iter().map(|line| format!("{line}\n")).collect();
+    assert_eq!(expected, actual);
+}
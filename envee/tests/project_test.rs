@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use tempfile::tempdir;
+
+use envee::project::Project;
+
+#[test]
+fn test_discover() {
+    let root = tempdir().unwrap();
+    write(root.path());
+    let sub = root.path().join("src/nested");
+    let output = envee(&sub, &["show"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        "export NAME='dev'\nexport DEBUG='true'\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    let output = envee(&sub, &["show", "-s", "prod"]);
+    assert_eq!(
+        "export NAME='prod'\nexport DEBUG='true'\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    // passed files replace the files and stage of the project
+    let output = envee(&sub, &["show", "-f", "../../envee.toml"]);
+    assert_eq!(
+        "export NAME='app'\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    let output = envee(&sub, &["run", "--", "sh", "-c", "echo $NAME"]);
+    assert_eq!("dev\n", String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn test_read() {
+    let root = tempdir().unwrap();
+    let path = root.path().join(".envee.toml");
+    fs::write(
+        &path,
+        "files = \"envee.toml\"\noverride = true\nschema = \"schema.toml\"\nkey_file = \"keys/key\"\n",
+    )
+    .unwrap();
+    let expected = Project {
+        files: vec![root.path().join("envee.toml")],
+        stage: None,
+        overrides: true,
+        schema: Some(root.path().join("schema.toml")),
+        key_file: Some(root.path().join("keys/key")),
+    };
+    assert_eq!(expected, Project::read(&path).unwrap());
+    fs::write(&path, "file = \"envee.toml\"\n").unwrap();
+    let error = Project::read(&path).unwrap_err();
+    assert_eq!(
+        format!("invalid project config {}", path.display()),
+        error.to_string()
+    );
+    assert_eq!("invalid setting: file", error.root_cause().to_string());
+}
+
+fn write(dir: &Path) {
+    fs::create_dir_all(dir.join("src/nested")).unwrap();
+    fs::write(
+        dir.join(".envee.toml"),
+        "files = [\"envee.toml\", \"local.env\"]\nstage = \"dev\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("envee.toml"),
+        "name = \"app\"\n\n[stages.dev]\nname = \"dev\"\n\n[stages.prod]\nname = \"prod\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("local.env"), "DEBUG=true\n").unwrap();
+}
+
+fn envee(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_envee"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
@@ -0,0 +1,166 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use tempfile::tempdir;
+
+use common::{message, pairs, resolve, resolve_dir};
+
+#[test]
+fn test_cmd() {
+    test(
+        &[
+            "token = { envee.cmd = \"printf 'secret\\\\n'\" }",
+            "raw = { envee.cmd = \"printf 'secret\\\\n'\", envee.trim = false }",
+            "literal = { envee.cmd = \"printf '%s' '${HOME}'\" }",
+            "url = \"https://${TOKEN}@host\"",
+        ],
+        &[
+            ("TOKEN", "secret"),
+            ("RAW", "secret\n"),
+            ("LITERAL", "${HOME}"),
+            ("URL", "https://secret@host"),
+        ],
+    );
+}
+
+#[test]
+fn test_file() {
+    let files: [(&str, &[&str]); 2] = [
+        (
+            "config/test.toml",
+            &["token = { envee.file = \"../secrets/token\" }"],
+        ),
+        ("secrets/token", &["  s3cr3t  "]),
+    ];
+    assert_eq!(
+        pairs(&[("TOKEN", "s3cr3t")]),
+        resolve_dir(&files, |root| envee::env::Resolver::new(vec![
+            root.join("config/test.toml")
+        ]))
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_errors() {
+    let error = resolve(
+        &[&["token = { envee.cmd = \"echo denied >&2; exit 3\" }"]],
+        |resolver| resolver,
+    )
+    .unwrap_err();
+    assert_eq!(
+        "failed to resolve TOKEN with cmd \"echo denied >&2; exit 3\": sh failed: denied",
+        message(&error)
+    );
+    let error = resolve(
+        &[&["token = { envee.cmd = \"sleep 5\", envee.timeout = 0.1 }"]],
+        |resolver| resolver,
+    )
+    .unwrap_err();
+    assert!(message(&error).ends_with("timed out after 0.1s"));
+    let error = resolve(&[&["token = { envee.file = \"missing\" }"]], |resolver| {
+        resolver
+    })
+    .unwrap_err();
+    assert!(message(&error).starts_with("failed to resolve TOKEN with file \"missing\""));
+    for lines in [
+        &["token = { envee.cmd = 1 }"][..],
+        &["token = { envee.cmd = \"true\", envee.join = \",\" }"],
+        &["token = { envee.value = \"a\", envee.trim = true }"],
+        &["token = { envee.cmd = \"true\", envee.options = {} }"],
+        &["token = { envee.cmd = \"true\", envee.timeout = 0 }"],
+        &["token = { envee.cmd = \"true\", envee.timeout = inf }"],
+        &["token = { envee.cmd = \"true\", envee.timeout = 1e300 }"],
+        &["token = { envee.provider = \"x\", envee.options = 1 }"],
+    ] {
+        assert!(resolve(&[lines], |resolver| resolver).is_err());
+    }
+    // only the envee table marks a value as provided, tables holding
+    // nothing but a source key are regular tables
+    test(
+        &[
+            "[build]",
+            "cmd = \"make\"",
+            "[log]",
+            "file = \"/etc/hostname\"",
+            "[cloud]",
+            "provider = \"aws\"",
+            "region = \"eu\"",
+        ],
+        &[
+            ("BUILD_CMD", "make"),
+            ("LOG_FILE", "/etc/hostname"),
+            ("CLOUD_PROVIDER", "aws"),
+            ("CLOUD_REGION", "eu"),
+        ],
+    );
+    let error = resolve(&[&["token = { envee.cmd = \"true\", other = 1 }"]], |r| r);
+    assert_eq!(
+        "other cannot be set next to a value",
+        message(&error.unwrap_err())
+    );
+}
+
+#[test]
+fn test_plugin() {
+    let bin = tempdir().unwrap();
+    let input = bin.path().join("input.json");
+    let script = format!(
+        "#!/bin/sh\ncat > '{}'\nprintf '{{\"value\": \"s3cr3t\"}}'\n",
+        input.display()
+    );
+    plugin(bin.path(), "echo", &script);
+    plugin(
+        bin.path(),
+        "deny",
+        "#!/bin/sh\nprintf '{\"error\": \"access denied\"}'\n",
+    );
+    let path = std::env::var("PATH").unwrap();
+    unsafe {
+        std::env::set_var("PATH", format!("{}:{path}", bin.path().display()));
+    }
+    test(
+        &[
+            "password = { envee.provider = \"echo\", envee.options = { path = \"db\", version = 2 } }",
+        ],
+        &[("PASSWORD", "s3cr3t")],
+    );
+    let input: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&input).unwrap()).unwrap();
+    assert_eq!(
+        serde_json::json!({ "key": "PASSWORD", "options": { "path": "db", "version": 2 } }),
+        input
+    );
+    let error = resolve(
+        &[&["password = { envee.provider = \"deny\" }"]],
+        |resolver| resolver,
+    )
+    .unwrap_err();
+    assert_eq!(
+        "failed to resolve PASSWORD with provider \"deny\": access denied",
+        message(&error)
+    );
+    let error = resolve(
+        &[&["password = { envee.provider = \"missing\" }"]],
+        |resolver| resolver,
+    )
+    .unwrap_err();
+    assert!(message(&error).contains("failed to run envee-provider-missing"));
+}
+
+fn plugin(dir: &std::path::Path, name: &str, script: &str) {
+    let path = dir.join(format!("envee-provider-{name}"));
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn test(lines: &[&str], expected: &[(&str, &str)]) {
+    assert_eq!(
+        pairs(expected),
+        resolve(&[lines], |resolver| resolver).unwrap()
+    );
+}
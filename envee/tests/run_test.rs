@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, ExitStatus, Stdio};
+
+use tempfile::tempdir;
+
+#[test]
+fn test_inherit() {
+    let env = run(&[]);
+    assert_eq!(Some("app"), env.get("NAME").map(String::as_str));
+    assert_eq!(Some("host"), env.get("ENVEE_TEST_HOST").map(String::as_str));
+    assert!(env.contains_key("PATH"));
+}
+
+#[test]
+fn test_isolated() {
+    let env = run(&["--isolated"]);
+    assert_eq!(vec!["NAME"], keys(&env));
+    assert_eq!(Some("app"), env.get("NAME").map(String::as_str));
+}
+
+#[test]
+fn test_isolated_inherit() {
+    let env = run(&["--isolated", "--inherit", "PATH,ENVEE_TEST_*"]);
+    assert_eq!(
+        vec!["ENVEE_TEST_HOST", "ENVEE_TEST_OTHER", "NAME", "PATH"],
+        keys(&env)
+    );
+    let env = run(&[
+        "--isolated",
+        "--inherit",
+        "ENVEE_TEST_HOS?",
+        "--inherit",
+        "PATH",
+    ]);
+    assert_eq!(vec!["ENVEE_TEST_HOST", "NAME", "PATH"], keys(&env));
+}
+
+#[test]
+fn test_passphrase() {
+    let env = run(&[]);
+    assert!(!env.contains_key("ENVEE_PASSPHRASE"));
+    assert!(!env.contains_key("ENVEE_NEW_PASSPHRASE"));
+    let env = run(&["--isolated", "--inherit", "ENVEE_*"]);
+    assert_eq!(
+        vec!["ENVEE_TEST_HOST", "ENVEE_TEST_OTHER", "NAME"],
+        keys(&env)
+    );
+}
+
+#[test]
+fn test_exit_code() {
+    assert_eq!(Some(0), status(&[], "exit 0").code());
+    assert_eq!(Some(7), status(&[], "exit 7").code());
+    assert_eq!(Some(143), status(&[], "kill -TERM $$").code());
+    assert_eq!(Some(3), status(&["--exec"], "exit 3").code());
+}
+
+#[test]
+fn test_forward() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "name = \"app\"\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["run", "-f"])
+        .arg(&path)
+        .args([
+            "--",
+            "sh",
+            "-c",
+            // bounded so a failing test never leaves the script running
+            "trap 'exit 42' TERM; echo ready; for i in $(seq 100); do sleep 0.1; done",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::default();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    assert_eq!("ready\n", line);
+    let kill = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    assert_eq!(Some(42), child.wait().unwrap().code());
+}
+
+fn status(args: &[&str], script: &str) -> ExitStatus {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "name = \"app\"\n").unwrap();
+    Command::new(env!("CARGO_BIN_EXE_envee"))
+        .arg("run")
+        .args(args)
+        .arg("-f")
+        .arg(&path)
+        .args(["--", "sh", "-c", script])
+        .status()
+        .unwrap()
+}
+
+fn run(args: &[&str]) -> HashMap<String, String> {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "name = \"app\"\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .env("ENVEE_TEST_HOST", "host")
+        .env("ENVEE_TEST_OTHER", "other")
+        .env("ENVEE_PASSPHRASE", "passphrase")
+        .env("ENVEE_NEW_PASSPHRASE", "passphrase")
+        .arg("run")
+        .args(args)
+        .arg("-f")
+        .arg(&path)
+        .args(["--", "env"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn keys(env: &HashMap<String, String>) -> Vec<&str> {
+    let mut result: Vec<_> = env.keys().map(String::as_str).collect();
+    result.sort();
+    result
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use tempfile::tempdir;
+
+use common::{message, pairs, resolve, resolve_dir};
+use envee::env::Resolver;
+
+#[test]
+fn test_types() {
+    let valid = [
+        ("string", "anything"),
+        ("int", "-42"),
+        ("bool", "Yes"),
+        ("bool", "0"),
+        ("url", "postgres://localhost/db"),
+        ("port", "65535"),
+    ];
+    for (kind, value) in valid {
+        assert_eq!(pairs(&[("VALUE", value)]), typed(kind, value).unwrap());
+    }
+    let invalid = [
+        ("int", "4.2", "an int"),
+        ("bool", "maybe", "a bool"),
+        ("url", "localhost", "a url"),
+        ("url", "http://", "a url"),
+        ("port", "0", "a port"),
+        ("port", "65536", "a port"),
+    ];
+    for (kind, value, expected) in invalid {
+        let error = typed(kind, value).unwrap_err();
+        let violation = error.to_string().lines().nth(2).unwrap().to_string();
+        assert_eq!(
+            format!("VALUE must be {expected}, got {value:?}"),
+            violation
+        );
+    }
+}
+
+#[test]
+fn test_rules() {
+    let result = resolve(
+        &[&[
+            "level = \"info\"",
+            "id = \"a1\"",
+            "url = \"http://${HOST}:${PORT}\"",
+            "[schema]",
+            "LEVEL = { type = \"enum\", values = [\"debug\", \"info\"] }",
+            "ID = { type = \"regex\", pattern = \"[a-z][0-9]\" }",
+            "HOST = { default = \"localhost\" }",
+            "PORT = { type = \"port\", default = 8080, required = true }",
+            "URL = { type = \"url\", description = \"where to connect\" }",
+        ]],
+        |r| r,
+    );
+    assert_eq!(
+        pairs(&[
+            ("LEVEL", "info"),
+            ("ID", "a1"),
+            ("URL", "http://localhost:8080"),
+            ("HOST", "localhost"),
+            ("PORT", "8080"),
+        ]),
+        result.unwrap()
+    );
+}
+
+#[test]
+fn test_violations() {
+    let error = resolve(
+        &[&[
+            "level = \"loud\"",
+            "id = \"a12\"",
+            "api_token = \"t\"",
+            "empty = \"\"",
+            "[schema]",
+            "LEVEL = { type = \"enum\", values = [\"debug\", \"info\"] }",
+            "ID = { type = \"regex\", pattern = \"[a-z][0-9]\" }",
+            "API_TOKEN = \"int\"",
+            "EMPTY = { type = \"int\", required = true }",
+            "OPTIONAL = \"int\"",
+            "HOST = { required = true, description = \"where to connect\" }",
+        ]],
+        |r| r,
+    )
+    .unwrap_err();
+    let lines: Vec<_> = error
+        .to_string()
+        .lines()
+        .filter(|line| !line.starts_with(' ') && !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    assert_eq!(
+        vec![
+            "5 variables do not match the schema",
+            "LEVEL must be one of debug, info, got \"loud\"",
+            "ID must be a value matching \"[a-z][0-9]\", got \"a12\"",
+            "API_TOKEN must be an int, got ****",
+            "EMPTY is required but not set",
+            "HOST is required but not set",
+        ],
+        lines
+    );
+    assert!(error.to_string().contains("^ where to connect"));
+}
+
+#[test]
+fn test_invalid() {
+    let cases: [(&[&str], &str); 7] = [
+        (&["schema = 1"], "schema must be a table"),
+        (
+            &["[schema]", "A = 1"],
+            "schema of A must be a type or a table",
+        ),
+        (
+            &["[schema]", "A = \"float\""],
+            "invalid type: float, expected string, int, bool, url, port, enum or regex",
+        ),
+        (
+            &["[schema]", "A = { type = \"enum\" }"],
+            "enum must have a list of values",
+        ),
+        (
+            &["[schema]", "A = { pattern = \"a\" }"],
+            "pattern can only be set for regex",
+        ),
+        (
+            &["[schema]", "A = { kind = \"int\" }"],
+            "invalid schema attribute: A.kind",
+        ),
+        (
+            &["[schema]", "\"A=1; touch x; B\" = { default = \"x\" }"],
+            "invalid environment variable name: A=1; touch x; B",
+        ),
+    ];
+    for (lines, expected) in cases {
+        let error = resolve(&[lines], |r| r).unwrap_err();
+        assert_eq!(expected, message(&error));
+    }
+}
+
+#[test]
+fn test_schema_file() {
+    let result = resolve_dir(
+        &[
+            (
+                "test.toml",
+                &[
+                    "port = \"80\"",
+                    "[schema]",
+                    "PORT = \"int\"",
+                    "HOST = \"url\"",
+                ][..],
+            ),
+            (
+                "schema.yaml",
+                &["PORT: port", "HOST:", "  default: localhost"],
+            ),
+        ],
+        |root| Resolver::new(vec![root.join("test.toml")]).schema(Some(root.join("schema.yaml"))),
+    );
+    assert_eq!(
+        pairs(&[("PORT", "80"), ("HOST", "localhost")]),
+        result.unwrap()
+    );
+}
+
+#[test]
+fn test_check() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "port = \"80\"\n\n[schema]\nPORT = \"port\"\n").unwrap();
+    let check = || {
+        Command::new(env!("CARGO_BIN_EXE_envee"))
+            .args(["check", "-f"])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let output = check();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    fs::write(&path, "port = \"http\"\n\n[schema]\nPORT = \"port\"\n").unwrap();
+    let output = check();
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with(
+        "1 variable does not match the schema\n\nPORT must be a port, got \"http\"\n"
+    ));
+}
+
+/// resolves a single value checked against a type
+fn typed(kind: &str, value: &str) -> anyhow::Result<Vec<(String, String)>> {
+    let value = format!("value = {value:?}");
+    let kind = format!("VALUE = {kind:?}");
+    resolve(&[&[&value, "[schema]", &kind]], |r| r)
+}
@@ -0,0 +1,221 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use tempfile::tempdir;
+
+use envee::env::Resolver;
+use envee::redact::Redactor;
+
+#[test]
+fn test_secret() {
+    let root = tempdir().unwrap();
+    let toml = root.path().join("test.toml");
+    let dotenv = root.path().join(".env");
+    fs::write(
+        &toml,
+        r#"
+name = "app"
+db_password = { envee.value = "p", envee.secret = false }
+api_token = "t"
+key = { envee.value = "k", envee.secret = true }
+hash = { envee.cmd = "echo h", envee.secret = true }
+paths = { envee.value = ["a", "b"], envee.join = ":", envee.secret = true }
+
+[db]
+envee = { secret = true }
+host = "localhost"
+user = "admin"
+"#,
+    )
+    .unwrap();
+    fs::write(&dotenv, "SESSION_SECRET=s\nDEBUG=1\n").unwrap();
+    let secrets = secrets(Resolver::new(vec![toml, dotenv]));
+    assert_eq!(
+        vec![
+            "API_TOKEN",
+            "KEY",
+            "HASH",
+            "PATHS",
+            "DB_HOST",
+            "DB_USER",
+            "SESSION_SECRET"
+        ],
+        secrets
+    );
+}
+
+#[test]
+fn test_patterns() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(
+        &path,
+        "envee = { prefix = \"APP_\", secrets = [\"APP_DB_*\"] }\napi_token = \"t\"\ndb = { user = \"admin\" }\n",
+    )
+    .unwrap();
+    assert_eq!(vec!["APP_DB_USER"], secrets(Resolver::new(vec![path])));
+}
+
+#[test]
+fn test_redact() {
+    let redactor = Redactor::new(["abc", "abcdef", "", "abc", "x"]);
+    assert_eq!(
+        b"****-****-****-ab".to_vec(),
+        redactor.redact(b"abcdef-abc-x-ab")
+    );
+    let mut output = Vec::default();
+    let input = Bytes(b"a abcdef abcy ab".to_vec());
+    redactor.copy(input, &mut output).unwrap();
+    assert_eq!(b"a **** ****y ab".to_vec(), output);
+    let mut output = Vec::default();
+    Redactor::default().copy(&b"abc"[..], &mut output).unwrap();
+    assert_eq!(b"abc".to_vec(), output);
+}
+
+#[test]
+fn test_show() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "name = \"app\"\napi_token = \"t0k3n\"\n").unwrap();
+    let output = envee(&path, &["show"]);
+    assert_eq!("export NAME='app'\nexport API_TOKEN='****'\n", output);
+    let output = envee(&path, &["show", "--reveal"]);
+    assert_eq!("export NAME='app'\nexport API_TOKEN='t0k3n'\n", output);
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["show", "-f"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        "warning: masked secrets: API_TOKEN, pass --reveal to show them\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+    // masked values would be taken for the real ones by other programs
+    for format in ["secret", "json", "dotenv"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+            .args(["show", "--format", format, "-f"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with(&format!(
+            "Error: secrets cannot be masked in {format} output, pass --reveal to include them: API_TOKEN\n"
+        )));
+    }
+    let output = envee(&path, &["show", "--format", "secret", "--reveal"]);
+    assert!(output.contains("API_TOKEN: dDBrM24=\n"), "{output}");
+    let output = envee(&path, &["explain", "API_TOKEN"]);
+    assert!(
+        output.starts_with("API_TOKEN = **** (secret)\n"),
+        "{output}"
+    );
+}
+
+#[test]
+fn test_derived() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    let other = root.path().join("other.toml");
+    fs::write(
+        &path,
+        "api_token = \"s3cr3t\"\nurl = \"https://${API_TOKEN}@host\"\nlogin = \"curl ${URL}\"\n",
+    )
+    .unwrap();
+    fs::write(&other, "url = \"https://host\"\n").unwrap();
+    // values built from secrets are masked where they include them
+    let output = envee(&path, &["show"]);
+    assert_eq!(
+        "export API_TOKEN='****'\nexport URL='https://****@host'\nexport LOGIN='curl https://****@host'\n",
+        output
+    );
+    let output = envee(&path, &["show", "--reveal"]);
+    assert!(output.contains("URL='https://s3cr3t@host'"), "{output}");
+    let output = envee(&path, &["explain", "URL"]);
+    assert!(
+        output.starts_with("URL = \"https://****@host\"\n"),
+        "{output}"
+    );
+    assert!(!output.contains("s3cr3t"), "{output}");
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["diff", "-a"])
+        .arg(&path)
+        .arg("-b")
+        .arg(&other)
+        .output()
+        .unwrap();
+    assert_eq!(
+        "- API_TOKEN = \"****\"\n~ URL = \"https://****@host\" -> \"https://host\"\n- LOGIN = \"curl https://****@host\"\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run() {
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    fs::write(&path, "name = \"app\"\napi_token = \"t0k3n\"\n").unwrap();
+    let script = "echo $NAME $API_TOKEN; printf t0k; printf '3n\\n' >&2; echo done";
+    let output = run(&path, &["--redact"], script);
+    assert!(output.status.success());
+    // a secret is only replaced when it is written to a single stream
+    assert_eq!(
+        "app ****\nt0kdone\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert_eq!("3n\n", String::from_utf8(output.stderr).unwrap());
+    let output = run(&path, &["--redact"], "printf $API_TOKEN >&2; exit 3");
+    assert_eq!(Some(3), output.status.code());
+    assert_eq!("****", String::from_utf8(output.stderr).unwrap());
+    let output = run(&path, &[], "echo $API_TOKEN");
+    assert_eq!("t0k3n\n", String::from_utf8(output.stdout).unwrap());
+}
+
+/// reads one byte at a time so secrets are split across reads
+struct Bytes(Vec<u8>);
+
+impl Read for Bytes {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0.remove(0);
+        Ok(1)
+    }
+}
+
+fn secrets(resolver: Resolver) -> Vec<String> {
+    resolver
+        .resolve()
+        .unwrap()
+        .into_iter()
+        .filter(|variable| variable.secret)
+        .map(|variable| variable.key)
+        .collect()
+}
+
+fn envee(path: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(args)
+        .arg("-f")
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[cfg(unix)]
+fn run(path: &Path, args: &[&str], script: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_envee"))
+        .arg("run")
+        .args(args)
+        .arg("-f")
+        .arg(path)
+        .args(["--", "sh", "-c", script])
+        .output()
+        .unwrap()
+}
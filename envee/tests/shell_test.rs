@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::tempdir;
+
+use envee::env::Resolver;
+
+const VALUES: &[&str] = &[
+    "name = \"plain\"",
+    "empty = \"\"",
+    "quotes = \"a \\\"double\\\" 'single' ‘smart’\"",
+    "dollar = \"$$HOME $${USER} $$(echo pwned)\"",
+    "backtick = \"`echo pwned`\"",
+    "backslash = 'C:\\path\\n \\'",
+    "newline = \"first\\nsecond\\n\"",
+    "control = \"tab\\there\\u0001\\r\"",
+];
+
+#[test]
+fn test_sh() {
+    test("sh", "export", &["-c", ". \"$1\" && env -0", "sh"]);
+}
+
+#[test]
+fn test_bash() {
+    test("bash", "export", &["-c", ". \"$1\" && env -0", "bash"]);
+    test("bash", "bash", &["-c", ". \"$1\" && env -0", "bash"]);
+}
+
+// shells that are not installed everywhere, run with `cargo test -- --ignored`
+// where they are
+
+#[test]
+#[ignore = "needs zsh"]
+fn test_zsh() {
+    test("zsh", "bash", &["-c", ". \"$1\" && env -0", "zsh"]);
+}
+
+#[test]
+#[ignore = "needs fish"]
+fn test_fish() {
+    test("fish", "fish", &["-c", "source $argv[1]; and env -0"]);
+}
+
+#[test]
+#[ignore = "needs pwsh"]
+fn test_powershell() {
+    test("pwsh", "powershell", &["-NoProfile", "-File"]);
+}
+
+/// evals the output of show in the shell and compares the environment it
+/// ends up with against the resolver, failing if the shell is not installed
+fn test(shell: &str, format: &str, args: &[&str]) {
+    if let Err(err) = Command::new(shell).arg("--version").output() {
+        panic!("{shell} is not installed: {err}");
+    }
+    let root = tempdir().unwrap();
+    let path = root.path().join("test.toml");
+    let mut file = File::create(&path).unwrap();
+    for line in VALUES {
+        writeln!(file, "{line}").unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .args(["show", "--format", format, "-f"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // powershell only runs scripts with the right extension, any other
+    // shell reads the file regardless
+    let script = root.path().join("script.ps1");
+    let mut text = output.stdout;
+    if shell == "pwsh" {
+        text.extend(b"env -0\n");
+    }
+    fs::write(&script, text).unwrap();
+
+    let actual = run(shell, args, &script);
+    let expected = Resolver::new(vec![path]).get().unwrap();
+    assert_eq!(VALUES.len(), expected.len());
+    for (key, value) in expected {
+        assert_eq!(Some(&value), actual.get(&key), "{shell} {format}: {key}");
+    }
+}
+
+fn run(shell: &str, args: &[&str], script: &Path) -> HashMap<String, String> {
+    let output = Command::new(shell).args(args).arg(script).output().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Lines};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use tempfile::tempdir;
+
+#[test]
+fn test_watch() {
+    let root = tempdir().unwrap();
+    let base = root.path().join("base.toml");
+    fs::write(&base, "name = \"a\"\nport = 1\n").unwrap();
+    fs::write(
+        root.path().join("envee.toml"),
+        "extends = \"base.toml\"\ndebug = true\n",
+    )
+    .unwrap();
+    // exits on its own after a while to not outlive a failing test
+    let script = "trap 'echo stop $NAME; exit 0' TERM; echo start $NAME $PORT; i=0; while [ $i -lt 100 ]; do sleep 0.1; i=$((i+1)); done";
+    let mut child = Command::new(env!("CARGO_BIN_EXE_envee"))
+        .current_dir(root.path())
+        .args([
+            "run",
+            "-f",
+            "envee.toml",
+            "--watch",
+            "--",
+            "sh",
+            "-c",
+            script,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+    assert_eq!("start a 1", next(&mut stdout));
+
+    pause();
+    fs::write(&base, "name = \"b\"\nhost = \"h\"\n").unwrap();
+    assert_eq!(
+        "envee: restarting, changed ~NAME, -PORT, +HOST",
+        next(&mut stderr)
+    );
+    assert_eq!("stop a", next(&mut stdout));
+    assert_eq!("start b", next(&mut stdout));
+
+    pause();
+    fs::write(&base, "name = \"${\"\n").unwrap();
+    assert!(
+        message(&mut stderr).starts_with("envee: not restarting"),
+        "{stderr:?}"
+    );
+
+    // the command is not found with this path, envee keeps waiting
+    pause();
+    fs::write(&base, "name = \"c\"\npath = \"/nonexistent\"\n").unwrap();
+    assert_eq!(
+        "envee: restarting, changed ~NAME, -HOST, +PATH",
+        message(&mut stderr)
+    );
+    assert_eq!("stop b", next(&mut stdout));
+    assert!(
+        message(&mut stderr).starts_with("envee: failed to restart sh"),
+        "{stderr:?}"
+    );
+
+    pause();
+    fs::write(&base, "name = \"d\"\n").unwrap();
+    assert_eq!(
+        "envee: restarting, changed ~NAME, -PATH",
+        message(&mut stderr)
+    );
+    assert_eq!("start d", next(&mut stdout));
+
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    assert_eq!("stop d", next(&mut stdout));
+    assert!(child.wait().unwrap().success());
+}
+
+/// lets modification times of rewritten files differ
+fn pause() {
+    thread::sleep(Duration::from_millis(200));
+}
+
+/// next line of envee, skipping the rest of multi line errors
+fn message<R: BufRead>(lines: &mut Lines<R>) -> String {
+    loop {
+        let line = next(lines);
+        if line.starts_with("envee: ") {
+            return line;
+        }
+    }
+}
+
+fn next<R: BufRead>(lines: &mut Lines<R>) -> String {
+    lines.next().unwrap().unwrap()
+}